@@ -0,0 +1,65 @@
+//! Compares mip generation through the byte-level fast path against the generic path, which
+//! accesses each pixel through `Image::get_color_at` and `Image::set_color_at`.
+
+use std::hint::black_box;
+
+use bevy_asset::RenderAssetUsages;
+use bevy_image::Image;
+use bevy_tileset_importer::importer::{MipFilter, bench};
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+use wgpu_types::{Extent3d, TextureDimension, TextureFormat};
+
+const TILE_SIZE: u32 = 128;
+
+/// Creates a full mip chain for a single tile, with a noisy base level.
+fn mip_chain(format: TextureFormat) -> Vec<Image> {
+    let base_extent = Extent3d {
+        width: TILE_SIZE,
+        height: TILE_SIZE,
+        depth_or_array_layers: 1,
+    };
+    let pixel_bytes = format.block_copy_size(None).unwrap() as usize;
+
+    let mut levels = (0..base_extent.max_mips(TextureDimension::D2))
+        .map(|m| {
+            Image::new_fill(
+                base_extent.mip_level_size(m, TextureDimension::D2),
+                TextureDimension::D2,
+                &vec![0; pixel_bytes],
+                format,
+                RenderAssetUsages::all(),
+            )
+        })
+        .collect::<Vec<_>>();
+
+    let base = levels[0].data.as_mut().unwrap();
+    for (i, byte) in base.iter_mut().enumerate() {
+        *byte = (i as u32).wrapping_mul(2_654_435_761).to_le_bytes()[3];
+    }
+    levels
+}
+
+fn bench_mips(c: &mut Criterion) {
+    for format in [
+        TextureFormat::Rgba8UnormSrgb,
+        TextureFormat::Rgba8Unorm,
+        TextureFormat::Rg8Unorm,
+        TextureFormat::R8Unorm,
+    ] {
+        let mut group = c.benchmark_group(format!("mips/{format:?}"));
+        for mip_filter in [MipFilter::Box, MipFilter::Lanczos3] {
+            for (path, generic) in [("bytes", false), ("color", true)] {
+                let mut levels = mip_chain(format);
+                group.bench_function(BenchmarkId::new(path, format!("{mip_filter:?}")), |b| {
+                    b.iter(|| {
+                        bench::generate_mips(black_box(&mut levels), mip_filter, generic).unwrap()
+                    });
+                });
+            }
+        }
+        group.finish();
+    }
+}
+
+criterion_group!(benches, bench_mips);
+criterion_main!(benches);
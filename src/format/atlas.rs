@@ -0,0 +1,223 @@
+use bevy_image::{Image, TextureAtlasLayout};
+use bevy_math::{URect, UVec2};
+use bincode::{Decode, Encode};
+use serde::{Deserialize, Serialize};
+use wgpu_types::{Extent3d, TextureDimension, TextureFormat};
+
+use super::{TilesetFileError, mip_level_bytes};
+use crate::TileIndex;
+
+/// How the tiles of a tileset are arranged in its texture once loaded.
+///
+/// The tile data in a tileset file is always stored tile by tile, so the layout only affects
+/// the [`Image`] created by the loader, and tile indices are the same in either layout.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Encode, Decode)]
+pub enum OutputLayout {
+    /// A 2D array texture with one tile per layer, as used by `TilemapChunk`.
+    #[default]
+    Array,
+    /// A single 2D texture with the tiles packed in rows of `columns`, left to right and top to
+    /// bottom. Each tile is surrounded by a gutter of `padding` pixels that repeats its edge
+    /// pixels, so that filtering near the tile edges does not bleed into neighbouring tiles.
+    ///
+    /// The loader adds a [`TextureAtlasLayout`] giving the rect of each tile. Block-compressed
+    /// texture formats are not supported, and mip levels are only generated while the tile size
+    /// and padding remain whole numbers of pixels.
+    Atlas { columns: u32, padding: u32 },
+}
+
+impl OutputLayout {
+    /// Returns the maximum number of mip levels for tiles of the given size in this layout.
+    pub fn max_mip_levels(&self, tile_size: UVec2) -> u32 {
+        match *self {
+            Self::Array => u32::MAX,
+            Self::Atlas { padding, .. } => {
+                // Every level must divide evenly, so that tiles stay aligned to their gutters.
+                1 + tile_size
+                    .x
+                    .trailing_zeros()
+                    .min(tile_size.y.trailing_zeros())
+                    .min(padding.trailing_zeros())
+            }
+        }
+    }
+}
+
+/// The placement of tiles in an [`OutputLayout::Atlas`].
+#[derive(Debug, Clone, Copy)]
+pub(super) struct AtlasGrid {
+    tile_size: UVec2,
+    columns: u32,
+    rows: u32,
+    padding: u32,
+}
+
+impl AtlasGrid {
+    pub(super) fn new(
+        tile_size: [u32; 2],
+        tile_count: TileIndex,
+        columns: u32,
+        padding: u32,
+    ) -> Self {
+        let tile_count = u32::from(tile_count).max(1);
+        let columns = columns.clamp(1, tile_count);
+
+        Self {
+            tile_size: tile_size.into(),
+            columns,
+            rows: tile_count.div_ceil(columns),
+            padding,
+        }
+    }
+
+    /// The distance between the corners of neighbouring tiles.
+    fn stride(&self) -> UVec2 {
+        self.tile_size + 2 * self.padding
+    }
+
+    /// The size of the atlas texture.
+    pub(super) fn size(&self) -> UVec2 {
+        self.stride() * UVec2::new(self.columns, self.rows)
+    }
+
+    /// The rect of a tile within the atlas texture, excluding its gutter.
+    fn tile_rect(&self, tile: u32) -> URect {
+        let cell = UVec2::new(tile % self.columns, tile / self.columns);
+        let min = cell * self.stride() + self.padding;
+        URect::from_corners(min, min + self.tile_size)
+    }
+
+    /// Creates the [`TextureAtlasLayout`] for the first `tile_count` tiles.
+    pub(super) fn layout(&self, tile_count: TileIndex) -> TextureAtlasLayout {
+        let mut layout = TextureAtlasLayout::new_empty(self.size());
+        for tile in 0..u32::from(tile_count) {
+            layout.add_texture(self.tile_rect(tile));
+        }
+        layout
+    }
+
+    /// Packs tile-major texture data into a 2D atlas image.
+    pub(super) fn pack(
+        &self,
+        tile_count: TileIndex,
+        texture_format: TextureFormat,
+        texture_mips: u32,
+        texture_data: &[u8],
+    ) -> Result<Image, TilesetFileError> {
+        if texture_format.is_compressed() {
+            return Err(TilesetFileError::CompressedAtlas(texture_format));
+        }
+        let pixel_bytes = texture_format
+            .block_copy_size(None)
+            .ok_or(TilesetFileError::InvalidData)? as usize;
+
+        let tile_extent = Extent3d {
+            width: self.tile_size.x,
+            height: self.tile_size.y,
+            depth_or_array_layers: 1,
+        };
+        let tile_mip_bytes = (0..texture_mips)
+            .map(|m| {
+                mip_level_bytes(
+                    texture_format,
+                    tile_extent.mip_level_size(m, TextureDimension::D2),
+                )
+                .ok_or(TilesetFileError::InvalidData)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let tile_bytes = tile_mip_bytes.iter().sum::<usize>();
+        if tile_bytes * usize::from(tile_count) != texture_data.len() {
+            return Err(TilesetFileError::InvalidData);
+        }
+
+        let size = self.size();
+        let mut data = Vec::new();
+        let mut mip_offset = 0;
+
+        for (m, &mip_bytes) in tile_mip_bytes.iter().enumerate() {
+            let level = AtlasGrid {
+                tile_size: self.tile_size >> m as u32,
+                padding: self.padding >> m,
+                ..*self
+            };
+            if level.tile_size.min_element() == 0 || level.stride() * (1 << m) != self.stride() {
+                return Err(TilesetFileError::InvalidData);
+            }
+
+            let row_bytes = level.size().x as usize * pixel_bytes;
+            let mut mip = vec![0; row_bytes * (size.y >> m) as usize];
+
+            for tile in 0..u32::from(tile_count) {
+                let start = tile as usize * tile_bytes + mip_offset;
+                level.copy_tile(
+                    &mut mip,
+                    row_bytes,
+                    pixel_bytes,
+                    tile,
+                    &texture_data[start..start + mip_bytes],
+                );
+            }
+
+            data.append(&mut mip);
+            mip_offset += mip_bytes;
+        }
+
+        let mut image = Image::new_uninit(
+            Extent3d {
+                width: size.x,
+                height: size.y,
+                depth_or_array_layers: 1,
+            },
+            TextureDimension::D2,
+            texture_format,
+            Default::default(),
+        );
+        image.data = Some(data);
+        image.texture_descriptor.mip_level_count = texture_mips;
+
+        Ok(image)
+    }
+
+    /// Copies a tile into its rect of an atlas mip level, then fills its gutter by extending the
+    /// edge pixels outwards.
+    fn copy_tile(
+        &self,
+        atlas: &mut [u8],
+        row_bytes: usize,
+        pixel_bytes: usize,
+        tile: u32,
+        tile_data: &[u8],
+    ) {
+        let rect = self.tile_rect(tile);
+        let (x0, x1) = (rect.min.x as usize, rect.max.x as usize);
+        let padding = self.padding as usize;
+        let tile_row_bytes = (x1 - x0) * pixel_bytes;
+
+        for (y, src) in (rect.min.y as usize..).zip(tile_data.chunks_exact(tile_row_bytes)) {
+            let row = &mut atlas[y * row_bytes..(y + 1) * row_bytes];
+            row[x0 * pixel_bytes..x1 * pixel_bytes].copy_from_slice(src);
+
+            for x in x0 - padding..x0 {
+                row.copy_within(x0 * pixel_bytes..(x0 + 1) * pixel_bytes, x * pixel_bytes);
+            }
+            for x in x1..x1 + padding {
+                row.copy_within((x1 - 1) * pixel_bytes..x1 * pixel_bytes, x * pixel_bytes);
+            }
+        }
+
+        let (y0, y1) = (rect.min.y as usize, rect.max.y as usize);
+        let cell = (x0 - padding) * pixel_bytes..(x1 + padding) * pixel_bytes;
+        for y in y0 - padding..y0 {
+            atlas.copy_within(
+                y0 * row_bytes + cell.start..y0 * row_bytes + cell.end,
+                y * row_bytes + cell.start,
+            );
+        }
+        for y in y1..y1 + padding {
+            atlas.copy_within(
+                (y1 - 1) * row_bytes + cell.start..(y1 - 1) * row_bytes + cell.end,
+                y * row_bytes + cell.start,
+            );
+        }
+    }
+}
@@ -0,0 +1,237 @@
+use std::{
+    io::{Read, Seek, SeekFrom, Write},
+    ops::Range,
+};
+
+use wgpu_types::TextureFormat;
+
+use super::{
+    CompressionScheme, Storage, TilesetFileError, TilesetFileHeader, TilesetFileMeta,
+    read_exact_vec,
+};
+use crate::{TileIndex, parallel::par_map};
+
+/// The offsets of each compressed chunk, relative to the start of the chunk data.
+///
+/// On disk this is stored as the compressed length of each chunk as a little-endian `u64`, with
+/// chunks ordered by tile and then by mip level.
+#[derive(Debug)]
+pub(super) struct ChunkTable {
+    offsets: Vec<u64>,
+}
+
+impl ChunkTable {
+    /// Reads a table of `count` chunks. The table grows as it is read, so a corrupt `count` fails
+    /// at the end of the input rather than on allocation.
+    pub(super) fn read(mut reader: impl Read, count: usize) -> Result<Self, TilesetFileError> {
+        let mut offsets = vec![0u64];
+
+        let mut len = [0; 8];
+        for _ in 0..count {
+            reader.read_exact(&mut len)?;
+            let end = offsets[offsets.len() - 1]
+                .checked_add(u64::from_le_bytes(len))
+                .ok_or(TilesetFileError::InvalidData)?;
+            offsets.push(end);
+        }
+
+        Ok(Self { offsets })
+    }
+
+    /// Writes the table for the given compressed chunks.
+    pub(super) fn write(
+        chunks: &[Vec<u8>],
+        mut writer: impl Write,
+    ) -> Result<(), TilesetFileError> {
+        for chunk in chunks {
+            writer.write_all(&(chunk.len() as u64).to_le_bytes())?;
+        }
+        Ok(())
+    }
+
+    /// The number of chunks in the table.
+    fn len(&self) -> usize {
+        self.offsets.len() - 1
+    }
+
+    /// The total length of the chunk data.
+    pub(super) fn data_len(&self) -> u64 {
+        self.offsets[self.offsets.len() - 1]
+    }
+
+    /// The byte range of a single chunk within the chunk data.
+    fn chunk(&self, index: usize) -> Range<u64> {
+        self.offsets[index]..self.offsets[index + 1]
+    }
+
+    /// Splits the chunk data into compressed chunks, paired with their decompressed lengths.
+    pub(super) fn split<'a>(
+        &self,
+        data: &'a [u8],
+        tile_mip_bytes: &[usize],
+    ) -> Vec<(&'a [u8], usize)> {
+        (0..self.len())
+            .map(|i| {
+                let Range { start, end } = self.chunk(i);
+                (
+                    &data[start as usize..end as usize],
+                    tile_mip_bytes[i % tile_mip_bytes.len()],
+                )
+            })
+            .collect()
+    }
+}
+
+/// Returns the range of each chunk within the uncompressed texture data.
+pub(super) fn chunk_ranges(tile_mip_bytes: &[usize], tile_count: TileIndex) -> Vec<Range<usize>> {
+    let mut start = 0;
+    (0..tile_count)
+        .flat_map(|_| tile_mip_bytes)
+        .map(|len| {
+            let range = start..start + len;
+            start = range.end;
+            range
+        })
+        .collect()
+}
+
+/// Decompresses `(chunk, decompressed_len)` pairs in parallel, and concatenates the results.
+pub(super) fn decompress_chunks(
+    compression: CompressionScheme,
+    chunks: &[(&[u8], usize)],
+) -> Result<Vec<u8>, TilesetFileError> {
+    let chunks = par_map(chunks, |&(chunk, len)| compression.decompress(chunk, len))
+        .into_iter()
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(chunks.concat())
+}
+
+/// Random access to the tiles and mip levels of a tileset file written with
+/// [`Storage::Chunked`], without decompressing the rest of the texture.
+pub struct ChunkedTilesetReader<R> {
+    reader: R,
+    header: TilesetFileHeader,
+    meta: TilesetFileMeta,
+    tile_mip_bytes: Vec<usize>,
+    table: ChunkTable,
+    data_start: u64,
+}
+
+impl<R: Read + Seek> ChunkedTilesetReader<R> {
+    /// Reads the header, metadata, and chunk table of a tileset file.
+    ///
+    /// Returns [`TilesetFileError::NotChunked`] if the file was not written with
+    /// [`Storage::Chunked`], or [`TilesetFileError::InvalidData`] if the file has no mip levels or
+    /// its chunk table does not hold one chunk per tile and mip level.
+    pub fn new(mut reader: R) -> Result<Self, TilesetFileError> {
+        let header = TilesetFileHeader::read(&mut reader)?;
+        if header.storage != Storage::Chunked {
+            return Err(TilesetFileError::NotChunked);
+        }
+
+        let meta = TilesetFileMeta::read(&header, &mut reader)?;
+        // Tiles are read as a run of chunks from their first to their last mip level
+        if meta.texture_mips == 0 {
+            return Err(TilesetFileError::InvalidData);
+        }
+
+        let tile_mip_bytes = meta.tile_mip_bytes()?;
+        let table = ChunkTable::read(&mut reader, meta.chunk_count())?;
+        if table.len() != tile_mip_bytes.len() * usize::from(meta.tile_count) {
+            return Err(TilesetFileError::InvalidData);
+        }
+        let data_start = reader.stream_position()?;
+
+        Ok(Self {
+            reader,
+            header,
+            meta,
+            tile_mip_bytes,
+            table,
+            data_start,
+        })
+    }
+
+    pub fn header(&self) -> &TilesetFileHeader {
+        &self.header
+    }
+
+    pub fn tile_size(&self) -> [u32; 2] {
+        self.meta.tile_size
+    }
+
+    pub fn tile_count(&self) -> TileIndex {
+        self.meta.tile_count
+    }
+
+    pub fn texture_format(&self) -> TextureFormat {
+        self.meta.texture_format
+    }
+
+    pub fn texture_mips(&self) -> u32 {
+        self.meta.texture_mips
+    }
+
+    /// Reads every mip level of a single tile, laid out as in
+    /// [`TilesetFile::texture_data`](super::TilesetFile::texture_data).
+    pub fn read_tile(&mut self, tile_index: TileIndex) -> Result<Vec<u8>, TilesetFileError> {
+        if tile_index >= self.meta.tile_count {
+            return Err(TilesetFileError::TileOutOfRange {
+                tile_index,
+                tile_count: self.meta.tile_count,
+            });
+        }
+
+        // The mip levels of a tile are adjacent, so they can be read all at once.
+        let mips = self.tile_mip_bytes.len();
+        let first = usize::from(tile_index) * mips;
+        let start = self.table.chunk(first).start;
+        let end = self.table.chunk(first + mips - 1).end;
+
+        self.reader.seek(SeekFrom::Start(self.data_start + start))?;
+        let data = read_exact_vec(&mut self.reader, end - start)?;
+
+        let chunks = (first..first + mips)
+            .zip(&self.tile_mip_bytes)
+            .map(|(i, &len)| {
+                let Range { start: a, end: b } = self.table.chunk(i);
+                (&data[(a - start) as usize..(b - start) as usize], len)
+            })
+            .collect::<Vec<_>>();
+
+        decompress_chunks(self.header.compression, &chunks)
+    }
+
+    /// Reads a single mip level of every tile, ordered by tile index.
+    pub fn read_mip(&mut self, level: u32) -> Result<Vec<u8>, TilesetFileError> {
+        if level >= self.meta.texture_mips {
+            return Err(TilesetFileError::MipOutOfRange {
+                level,
+                mip_count: self.meta.texture_mips,
+            });
+        }
+
+        let mips = self.tile_mip_bytes.len();
+        let level = level as usize;
+
+        let data = (0..usize::from(self.meta.tile_count))
+            .map(|tile| {
+                let Range { start, end } = self.table.chunk(tile * mips + level);
+                self.reader.seek(SeekFrom::Start(self.data_start + start))?;
+                read_exact_vec(&mut self.reader, end - start)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let chunks = data
+            .iter()
+            .map(|chunk| (chunk.as_slice(), self.tile_mip_bytes[level]))
+            .collect::<Vec<_>>();
+
+        decompress_chunks(self.header.compression, &chunks)
+    }
+
+    /// Returns the underlying reader.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
@@ -0,0 +1,135 @@
+use std::io::{self, Read, Write};
+
+use serde::{Deserialize, Serialize};
+
+use super::TilesetFileError;
+
+/// The compression to apply when writing a tileset file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(from = "CompressionSetting")]
+pub enum Compression {
+    /// Leave the data uncompressed.
+    None,
+    /// Deflate with a [compression level][flate2::Compression] from 0-9. 0 leaves the data
+    /// uncompressed, and 9 means "take as long as you want".
+    Deflate(u32),
+    /// Zstandard with a compression level from 1-22, or 0 to use the zstd default.
+    Zstd(i32),
+    /// LZ4 frames. This compresses less than the alternatives, but is much faster to decode.
+    Lz4,
+}
+
+impl Compression {
+    /// Returns the scheme that will be recorded in the file header.
+    pub fn scheme(self) -> CompressionScheme {
+        match self {
+            Self::None => CompressionScheme::None,
+            Self::Deflate(_) => CompressionScheme::Deflate,
+            Self::Zstd(_) => CompressionScheme::Zstd,
+            Self::Lz4 => CompressionScheme::Lz4,
+        }
+    }
+
+    /// Compresses `data` into a new buffer.
+    pub(super) fn compress(self, data: &[u8]) -> io::Result<Vec<u8>> {
+        match self {
+            Self::None => Ok(data.to_vec()),
+            Self::Deflate(level) => {
+                let mut encoder =
+                    flate2::write::DeflateEncoder::new(Vec::new(), flate2::Compression::new(level));
+                encoder.write_all(data)?;
+                encoder.finish()
+            }
+            Self::Zstd(level) => zstd::encode_all(data, level),
+            Self::Lz4 => {
+                let mut encoder = lz4_flex::frame::FrameEncoder::new(Vec::new());
+                encoder.write_all(data)?;
+                encoder.finish().map_err(io::Error::from)
+            }
+        }
+    }
+}
+
+/// The accepted forms of a [`Compression`] setting. Settings written before the other backends
+/// were added give a bare deflate level.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum CompressionSetting {
+    Level(u32),
+    Named(NamedCompression),
+    // Self-describing formats may read a bare `None` as an empty option
+    Uncompressed(Option<Never>),
+}
+
+#[derive(Deserialize)]
+#[serde(rename = "Compression")]
+enum NamedCompression {
+    None,
+    Deflate(u32),
+    Zstd(i32),
+    Lz4,
+}
+
+#[derive(Deserialize)]
+enum Never {}
+
+impl From<CompressionSetting> for Compression {
+    fn from(setting: CompressionSetting) -> Self {
+        match setting {
+            CompressionSetting::Level(level) => Self::Deflate(level),
+            CompressionSetting::Named(NamedCompression::None) => Self::None,
+            CompressionSetting::Named(NamedCompression::Deflate(level)) => Self::Deflate(level),
+            CompressionSetting::Named(NamedCompression::Zstd(level)) => Self::Zstd(level),
+            CompressionSetting::Named(NamedCompression::Lz4) => Self::Lz4,
+            CompressionSetting::Uncompressed(_) => Self::None,
+        }
+    }
+}
+
+/// The compression scheme applied to the contents of a tileset file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum CompressionScheme {
+    None = 0,
+    Deflate = 1,
+    Zstd = 2,
+    Lz4 = 3,
+}
+
+impl CompressionScheme {
+    pub(super) fn from_u8(value: u8) -> Result<Self, TilesetFileError> {
+        match value {
+            0 => Ok(Self::None),
+            1 => Ok(Self::Deflate),
+            2 => Ok(Self::Zstd),
+            3 => Ok(Self::Lz4),
+            other => Err(TilesetFileError::UnknownCompression(other)),
+        }
+    }
+
+    /// Wraps `reader` in a decoder for this scheme.
+    pub(super) fn decoder<'a>(self, reader: impl Read + 'a) -> io::Result<Box<dyn Read + 'a>> {
+        Ok(match self {
+            Self::None => Box::new(reader),
+            Self::Deflate => Box::new(flate2::read::DeflateDecoder::new(reader)),
+            Self::Zstd => Box::new(zstd::Decoder::new(reader)?),
+            Self::Lz4 => Box::new(lz4_flex::frame::FrameDecoder::new(reader)),
+        })
+    }
+
+    /// Decompresses `data`, which must decompress to exactly `len` bytes.
+    ///
+    /// `len` comes from the file, so it is not trusted for the allocation size, and decoding
+    /// stops as soon as the output is known to be too long.
+    pub(super) fn decompress(self, data: &[u8], len: usize) -> Result<Vec<u8>, TilesetFileError> {
+        let mut decompressed = Vec::new();
+        self.decoder(data)?
+            .take(len as u64 + 1)
+            .read_to_end(&mut decompressed)?;
+
+        if decompressed.len() != len {
+            return Err(TilesetFileError::InvalidData);
+        }
+        Ok(decompressed)
+    }
+}
@@ -0,0 +1,154 @@
+use std::io::{Read, Write};
+
+use serde::{Deserialize, Serialize};
+
+use super::{CompressionScheme, TilesetFileError};
+
+/// The magic bytes at the start of every tileset file.
+pub const MAGIC: [u8; 4] = *b"BTSF";
+
+/// The current version of the tileset file format.
+///
+/// - Version 0 is the unversioned format written before the file header was introduced, which
+///   consisted of a single compression flag byte followed by the compressed file contents.
+/// - Version 1 added the header, and stores the file metadata separately from the texture data
+///   so that the texture data can be split into chunks with [`Storage::Chunked`].
+pub const FORMAT_VERSION: u16 = 1;
+
+/// The `[major, minor]` bevy version that texture formats are encoded against.
+///
+/// This must be updated whenever the `bevy_*` dependencies are upgraded.
+pub const BEVY_VERSION: [u16; 2] = [0, 18];
+
+/// The major `wgpu-types` version that texture formats are encoded against.
+///
+/// This must be updated whenever the `wgpu-types` dependency is upgraded.
+pub const WGPU_TYPES_VERSION: u16 = 27;
+
+/// The header at the start of a tileset file.
+///
+/// On disk this is the [`MAGIC`] bytes followed by each field in declaration order, with integers
+/// stored little-endian, and booleans stored as a single `0` or `1` byte.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TilesetFileHeader {
+    /// The version of the tileset file format.
+    pub version: u16,
+    /// The `[major, minor]` bevy version the file was written with, or `[0, 0]` if unknown.
+    pub bevy_version: [u16; 2],
+    /// The major `wgpu-types` version the file was written with, or `0` if unknown.
+    pub wgpu_types_version: u16,
+    /// The compression scheme applied to the texture data.
+    pub compression: CompressionScheme,
+    /// How the texture data is laid out in the file.
+    pub storage: Storage,
+    /// Whether the color channels of the texture are premultiplied by alpha.
+    pub premultiplied_alpha: bool,
+}
+
+/// How the texture data of a tileset file is laid out.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[repr(u8)]
+pub enum Storage {
+    /// The texture data is compressed as a single block. This gives the best compression ratio,
+    /// but the whole texture must be decompressed to access any part of it.
+    #[default]
+    Contiguous = 0,
+    /// Each mip level of each tile is compressed independently, and located using an offset
+    /// table. This allows individual tiles or mip levels to be read with
+    /// [`ChunkedTilesetReader`](super::ChunkedTilesetReader), and the whole texture to be
+    /// decompressed in parallel.
+    Chunked = 1,
+}
+
+impl Storage {
+    fn from_u8(value: u8) -> Result<Self, TilesetFileError> {
+        match value {
+            0 => Ok(Self::Contiguous),
+            1 => Ok(Self::Chunked),
+            other => Err(TilesetFileError::UnknownStorage(other)),
+        }
+    }
+}
+
+impl TilesetFileHeader {
+    /// The size of an encoded header in bytes, including the [`MAGIC`] bytes.
+    pub const SIZE: usize = 15;
+
+    /// Creates a header for the current format and dependency versions, for a texture with
+    /// straight alpha.
+    pub const fn current(compression: CompressionScheme, storage: Storage) -> Self {
+        Self {
+            version: FORMAT_VERSION,
+            bevy_version: BEVY_VERSION,
+            wgpu_types_version: WGPU_TYPES_VERSION,
+            compression,
+            storage,
+            premultiplied_alpha: false,
+        }
+    }
+
+    /// Returns `true` if the file was written by the current format version against the current
+    /// bevy and `wgpu-types` versions.
+    pub fn is_current(&self) -> bool {
+        self.version == FORMAT_VERSION
+            && self.bevy_version == BEVY_VERSION
+            && self.wgpu_types_version == WGPU_TYPES_VERSION
+    }
+
+    /// Reads a header, leaving `reader` positioned at the start of the file contents.
+    ///
+    /// This does not accept the unversioned (version 0) format, which is only handled by
+    /// [`TilesetFile::read`](super::TilesetFile::read).
+    pub fn read(mut reader: impl Read) -> Result<Self, TilesetFileError> {
+        let mut magic = [0; MAGIC.len()];
+        reader.read_exact(&mut magic)?;
+        if magic != MAGIC {
+            return Err(TilesetFileError::NotATilesetFile);
+        }
+        Self::read_after_magic(reader)
+    }
+
+    /// Reads the remainder of a header whose [`MAGIC`] bytes have already been consumed.
+    pub(super) fn read_after_magic(mut reader: impl Read) -> Result<Self, TilesetFileError> {
+        let mut bytes = [0; Self::SIZE];
+        reader.read_exact(&mut bytes[MAGIC.len()..])?;
+
+        let u16_at = |i: usize| u16::from_le_bytes([bytes[i], bytes[i + 1]]);
+
+        let version = u16_at(4);
+        if version > FORMAT_VERSION {
+            return Err(TilesetFileError::UnsupportedVersion(version));
+        }
+
+        let premultiplied_alpha = match bytes[14] {
+            0 => false,
+            1 => true,
+            _ => return Err(TilesetFileError::InvalidData),
+        };
+
+        Ok(Self {
+            version,
+            bevy_version: [u16_at(6), u16_at(8)],
+            wgpu_types_version: u16_at(10),
+            compression: CompressionScheme::from_u8(bytes[12])?,
+            storage: Storage::from_u8(bytes[13])?,
+            premultiplied_alpha,
+        })
+    }
+
+    /// Writes the header, including the [`MAGIC`] bytes.
+    pub fn write(&self, mut writer: impl Write) -> Result<(), TilesetFileError> {
+        let mut bytes = [0; Self::SIZE];
+        bytes[..4].copy_from_slice(&MAGIC);
+        bytes[4..6].copy_from_slice(&self.version.to_le_bytes());
+        bytes[6..8].copy_from_slice(&self.bevy_version[0].to_le_bytes());
+        bytes[8..10].copy_from_slice(&self.bevy_version[1].to_le_bytes());
+        bytes[10..12].copy_from_slice(&self.wgpu_types_version.to_le_bytes());
+        bytes[12] = self.compression as u8;
+        bytes[13] = self.storage as u8;
+        bytes[14] = self.premultiplied_alpha.into();
+
+        writer.write_all(&bytes)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,144 @@
+use std::io::{self, Read};
+
+use wgpu_types::TextureFormat;
+
+use super::{
+    OutputLayout, Storage, TilesetFileError, TilesetFileHeader, TilesetFileMeta,
+    UnversionedFileMeta, chunked::ChunkTable, decode_error, read_any_header,
+};
+use crate::TileIndex;
+
+/// A summary of a tileset file that can be read without decompressing the texture data.
+///
+/// This is intended for tooling that needs to inspect many tileset files quickly.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TilesetFileInfo {
+    pub header: TilesetFileHeader,
+    pub tile_size: [u32; 2],
+    pub tile_count: TileIndex,
+    pub texture_format: TextureFormat,
+    pub texture_mips: u32,
+    /// The name and number of tiles of each tile group.
+    pub tile_groups: Vec<(String, usize)>,
+    /// Each companion layer, along with the size of its texture data.
+    pub layers: Vec<TilesetFileLayerInfo>,
+    pub output_layout: OutputLayout,
+    pub max_array_layers: Option<u32>,
+    /// Whether the tiles were trimmed during import, and so have [`TileTrim`](super::TileTrim)
+    /// metadata.
+    pub trimmed: bool,
+    /// The size of the compressed main texture data in bytes.
+    ///
+    /// This is `None` for files in the unversioned (version 0) format, where the texture data is
+    /// compressed together with the rest of the file.
+    pub compressed_size: Option<u64>,
+    /// The size of the uncompressed main texture data in bytes.
+    pub uncompressed_size: u64,
+}
+
+/// A summary of a companion layer in a tileset file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TilesetFileLayerInfo {
+    pub name: String,
+    pub texture_format: TextureFormat,
+    /// The size of the compressed layer texture data in bytes.
+    pub compressed_size: u64,
+    /// The size of the uncompressed layer texture data in bytes.
+    pub uncompressed_size: u64,
+}
+
+impl TilesetFileInfo {
+    /// Reads the header and metadata of a tileset file, stopping before the texture data.
+    ///
+    /// Files in the unversioned (version 0) format store their metadata compressed together with
+    /// the texture data, so only the start of their contents is decompressed. Layer data is
+    /// stored after the main texture data, so files with layers are read up to the last layer,
+    /// but nothing is decompressed.
+    pub fn read(bytes: impl Read) -> Result<Self, TilesetFileError> {
+        let (header, mut bytes) = read_any_header(bytes)?;
+
+        let (meta, compressed_size, layer_sizes) = if header.version == 0 {
+            // The metadata fields precede the texture data, so we can stop decoding after them.
+            let mut decoder = header.compression.decoder(bytes)?;
+            let meta: UnversionedFileMeta =
+                bincode::decode_from_std_read(&mut decoder, bincode::config::standard())
+                    .map_err(|err| decode_error(&header, err))?;
+            (meta.into(), None, Vec::new())
+        } else {
+            let meta = TilesetFileMeta::read(&header, &mut bytes)?;
+            let compressed_size = match header.storage {
+                Storage::Contiguous => {
+                    let mut len = [0; 8];
+                    bytes.read_exact(&mut len)?;
+                    u64::from_le_bytes(len)
+                }
+                Storage::Chunked => ChunkTable::read(&mut bytes, meta.chunk_count())?.data_len(),
+            };
+
+            // Each layer follows the previous texture data, prefixed by its length
+            let mut skip = compressed_size;
+            let mut layer_sizes = Vec::with_capacity(meta.layers.len());
+            for _ in &meta.layers {
+                skip_exact(&mut bytes, skip)?;
+                let mut len = [0; 8];
+                bytes.read_exact(&mut len)?;
+                skip = u64::from_le_bytes(len);
+                layer_sizes.push(skip);
+            }
+
+            (meta, Some(compressed_size), layer_sizes)
+        };
+
+        let uncompressed_size = meta.texture_len()? as u64;
+        let layers = meta
+            .layers
+            .iter()
+            .zip(layer_sizes)
+            .map(|(layer, compressed_size)| {
+                Ok(TilesetFileLayerInfo {
+                    name: layer.name.clone(),
+                    texture_format: layer.texture_format,
+                    compressed_size,
+                    uncompressed_size: meta.format_texture_len(layer.texture_format)? as u64,
+                })
+            })
+            .collect::<Result<_, TilesetFileError>>()?;
+        let TilesetFileMeta {
+            tile_size,
+            tile_count,
+            tile_groups,
+            texture_format,
+            texture_mips,
+            layers: _,
+            output_layout,
+            max_array_layers,
+            tile_trims,
+        } = meta;
+
+        Ok(Self {
+            header,
+            tile_size,
+            tile_count,
+            texture_format,
+            texture_mips,
+            tile_groups: tile_groups
+                .into_iter()
+                .map(|(name, tiles)| (name, tiles.len()))
+                .collect(),
+            layers,
+            output_layout,
+            max_array_layers,
+            trimmed: !tile_trims.is_empty(),
+            compressed_size,
+            uncompressed_size,
+        })
+    }
+}
+
+/// Reads and discards `len` bytes.
+fn skip_exact(reader: impl Read, len: u64) -> io::Result<()> {
+    if io::copy(&mut reader.take(len), &mut io::sink())? != len {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+    Ok(())
+}
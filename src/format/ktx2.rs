@@ -0,0 +1,543 @@
+use std::{
+    io::{Read, Write},
+    num::NonZeroU8,
+};
+
+use ::ktx2::{
+    ChannelTypeQualifiers, ColorModel, ColorPrimaries, DataFormatFlags, DfdBlockBasic,
+    DfdBlockHeaderBasic, DfdHeader, Format, Header, Index, LevelIndex, Reader, SampleInformation,
+    SupercompressionScheme, TransferFunction,
+};
+use wgpu_types::TextureFormat;
+
+use super::{OutputLayout, TileGroupData, TilesetFile, TilesetFileError, TilesetFileMeta};
+use crate::TileIndex;
+
+/// The key/value data key that tile groups are stored under.
+///
+/// The value is the [`TilesetFile::tile_groups`] serialized as RON, so that it is readable in
+/// standard texture viewers.
+pub const KTX2_TILE_GROUPS_KEY: &str = "bevy_tileset_importer.tile_groups";
+
+/// The key/value data key that [`TilesetFile::max_array_layers`] is stored under, as a decimal
+/// number. The key is omitted if the texture is not split into pages.
+pub const KTX2_MAX_ARRAY_LAYERS_KEY: &str = "bevy_tileset_importer.max_array_layers";
+
+/// The NUL-terminated value of the standard `KTXwriter` key.
+const KTX2_WRITER: &str = concat!(env!("CARGO_PKG_NAME"), " ", env!("CARGO_PKG_VERSION"), "\0");
+
+// Channel ids for the `RGBSDA` color model.
+const R: u8 = 0;
+const G: u8 = 1;
+const B: u8 = 2;
+const A: u8 = 15;
+
+/// How the texels of a format are described in the data format descriptor.
+enum Samples {
+    /// Unsigned normalized channels of equal size, in the order they appear in memory.
+    Unorm(&'static [u8]),
+    /// Signed floating point channels of equal size, in the order they appear in memory.
+    Float(&'static [u8]),
+    /// A block-compressed format, with the block divided evenly between the listed channels.
+    Block(ColorModel, &'static [u8]),
+}
+
+/// The texture formats that can be stored in a KTX2 file, along with their Vulkan formats.
+const FORMATS: &[(TextureFormat, Format, Samples)] = &[
+    (
+        TextureFormat::R8Unorm,
+        Format::R8_UNORM,
+        Samples::Unorm(&[R]),
+    ),
+    (
+        TextureFormat::Rg8Unorm,
+        Format::R8G8_UNORM,
+        Samples::Unorm(&[R, G]),
+    ),
+    (
+        TextureFormat::Rgba8Unorm,
+        Format::R8G8B8A8_UNORM,
+        Samples::Unorm(&[R, G, B, A]),
+    ),
+    (
+        TextureFormat::Rgba8UnormSrgb,
+        Format::R8G8B8A8_SRGB,
+        Samples::Unorm(&[R, G, B, A]),
+    ),
+    (
+        TextureFormat::Bgra8Unorm,
+        Format::B8G8R8A8_UNORM,
+        Samples::Unorm(&[B, G, R, A]),
+    ),
+    (
+        TextureFormat::Bgra8UnormSrgb,
+        Format::B8G8R8A8_SRGB,
+        Samples::Unorm(&[B, G, R, A]),
+    ),
+    (
+        TextureFormat::R16Unorm,
+        Format::R16_UNORM,
+        Samples::Unorm(&[R]),
+    ),
+    (
+        TextureFormat::Rg16Unorm,
+        Format::R16G16_UNORM,
+        Samples::Unorm(&[R, G]),
+    ),
+    (
+        TextureFormat::Rgba16Unorm,
+        Format::R16G16B16A16_UNORM,
+        Samples::Unorm(&[R, G, B, A]),
+    ),
+    (
+        TextureFormat::R16Float,
+        Format::R16_SFLOAT,
+        Samples::Float(&[R]),
+    ),
+    (
+        TextureFormat::Rg16Float,
+        Format::R16G16_SFLOAT,
+        Samples::Float(&[R, G]),
+    ),
+    (
+        TextureFormat::Rgba16Float,
+        Format::R16G16B16A16_SFLOAT,
+        Samples::Float(&[R, G, B, A]),
+    ),
+    (
+        TextureFormat::R32Float,
+        Format::R32_SFLOAT,
+        Samples::Float(&[R]),
+    ),
+    (
+        TextureFormat::Rg32Float,
+        Format::R32G32_SFLOAT,
+        Samples::Float(&[R, G]),
+    ),
+    (
+        TextureFormat::Rgba32Float,
+        Format::R32G32B32A32_SFLOAT,
+        Samples::Float(&[R, G, B, A]),
+    ),
+    (
+        TextureFormat::Bc1RgbaUnorm,
+        Format::BC1_RGBA_UNORM_BLOCK,
+        Samples::Block(ColorModel::BC1A, &[1]),
+    ),
+    (
+        TextureFormat::Bc1RgbaUnormSrgb,
+        Format::BC1_RGBA_SRGB_BLOCK,
+        Samples::Block(ColorModel::BC1A, &[1]),
+    ),
+    (
+        TextureFormat::Bc3RgbaUnorm,
+        Format::BC3_UNORM_BLOCK,
+        Samples::Block(ColorModel::BC3, &[A, 0]),
+    ),
+    (
+        TextureFormat::Bc3RgbaUnormSrgb,
+        Format::BC3_SRGB_BLOCK,
+        Samples::Block(ColorModel::BC3, &[A, 0]),
+    ),
+    (
+        TextureFormat::Bc4RUnorm,
+        Format::BC4_UNORM_BLOCK,
+        Samples::Block(ColorModel::BC4, &[0]),
+    ),
+    (
+        TextureFormat::Bc5RgUnorm,
+        Format::BC5_UNORM_BLOCK,
+        Samples::Block(ColorModel::BC5, &[0, 1]),
+    ),
+    (
+        TextureFormat::Bc7RgbaUnorm,
+        Format::BC7_UNORM_BLOCK,
+        Samples::Block(ColorModel::BC7, &[0]),
+    ),
+    (
+        TextureFormat::Bc7RgbaUnormSrgb,
+        Format::BC7_SRGB_BLOCK,
+        Samples::Block(ColorModel::BC7, &[0]),
+    ),
+];
+
+impl TilesetFile {
+    /// Reads a KTX2 2D array texture, treating each array layer as a tile.
+    ///
+    /// Tile groups are read from the [`KTX2_TILE_GROUPS_KEY`] key/value data if present, so files
+    /// produced by other tools load with no groups. Likewise, the texture is only split into pages
+    /// if [`KTX2_MAX_ARRAY_LAYERS_KEY`] is present. Zstandard and zlib supercompression are
+    /// supported, but Basis Universal textures are not.
+    pub fn read_ktx2(bytes: &[u8]) -> Result<Self, TilesetFileError> {
+        let reader = Reader::new(bytes)?;
+        let header = reader.header();
+
+        let (texture_format, _, _) = FORMATS
+            .iter()
+            .find(|(_, format, _)| Some(*format) == header.format)
+            .ok_or(TilesetFileError::UnsupportedKtx2("unsupported vkFormat"))?;
+
+        if header.pixel_height == 0 || header.pixel_depth != 0 {
+            return Err(TilesetFileError::UnsupportedKtx2("not a 2D texture"));
+        }
+        if header.face_count != 1 {
+            return Err(TilesetFileError::UnsupportedKtx2(
+                "cubemaps are not supported",
+            ));
+        }
+
+        let layers = header.layer_count.max(1);
+        let meta = TilesetFileMeta {
+            tile_size: [header.pixel_width, header.pixel_height],
+            tile_count: TileIndex::try_from(layers)
+                .map_err(|_| TilesetFileError::TooManyTiles(layers))?,
+            tile_groups: read_tile_groups(&reader)?,
+            texture_format: *texture_format,
+            texture_mips: header.level_count.max(1),
+            layers: Vec::new(),
+            output_layout: OutputLayout::Array,
+            max_array_layers: read_max_array_layers(&reader)?,
+            tile_trims: Vec::new(),
+        };
+
+        let tile_mip_bytes = meta.tile_mip_bytes()?;
+        let levels = reader
+            .levels()
+            .zip(&tile_mip_bytes)
+            .map(|(level, &len)| {
+                let level_len = len * usize::from(meta.tile_count);
+                let data = supercompression_decompress(header, level.data, level_len)?;
+
+                if data.len() == level_len {
+                    Ok(data)
+                } else {
+                    Err(TilesetFileError::InvalidData)
+                }
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // KTX2 levels contain every layer of a single mip level, but tileset files store every
+        // mip level of a single tile together.
+        let mut texture_data = Vec::with_capacity(meta.texture_len()?);
+        for tile in 0..usize::from(meta.tile_count) {
+            for (level, &len) in levels.iter().zip(&tile_mip_bytes) {
+                texture_data.extend_from_slice(&level[tile * len..(tile + 1) * len]);
+            }
+        }
+
+        Ok(Self {
+            premultiplied_alpha: read_premultiplied_alpha(&reader)?,
+            ..Self::from_meta(meta, texture_data)
+        })
+    }
+
+    /// Writes the tileset as a KTX2 2D array texture, with one array layer per tile.
+    ///
+    /// Tile groups are stored as key/value data under [`KTX2_TILE_GROUPS_KEY`], along with
+    /// [`TilesetFile::max_array_layers`] under [`KTX2_MAX_ARRAY_LAYERS_KEY`]. The level data is
+    /// not supercompressed. Tilesets with companion layers, [`OutputLayout::Atlas`], or
+    /// [`TileTrim`](super::TileTrim) metadata are not supported.
+    pub fn write_ktx2(&self, mut writer: impl Write) -> Result<(), TilesetFileError> {
+        if !self.layers.is_empty() {
+            return Err(TilesetFileError::Ktx2Layers);
+        }
+        if self.output_layout != OutputLayout::Array {
+            return Err(TilesetFileError::Ktx2Atlas);
+        }
+        if !self.tile_trims.is_empty() {
+            return Err(TilesetFileError::Ktx2TileTrims);
+        }
+
+        let (_, format, samples) = FORMATS
+            .iter()
+            .find(|(texture_format, _, _)| *texture_format == self.texture_format)
+            .ok_or(TilesetFileError::UnsupportedKtx2Format(self.texture_format))?;
+
+        let meta = self.meta();
+        let tile_mip_bytes = meta.tile_mip_bytes()?;
+        if meta.texture_len()? != self.texture_data.len() {
+            return Err(TilesetFileError::InvalidData);
+        }
+
+        let block_size = self
+            .texture_format
+            .block_copy_size(None)
+            .ok_or(TilesetFileError::InvalidData)?;
+        let dfd = data_format_descriptor(
+            self.texture_format,
+            block_size,
+            samples,
+            self.premultiplied_alpha,
+        );
+        let max_array_layers = self.max_array_layers.map(|max| format!("{max}\0"));
+        let tile_groups = tile_groups_value(&self.tile_groups);
+        let kvd = key_value_data(
+            &[
+                Some(("KTXwriter", KTX2_WRITER.as_bytes())),
+                max_array_layers
+                    .as_ref()
+                    .map(|value| (KTX2_MAX_ARRAY_LAYERS_KEY, value.as_bytes())),
+                Some((KTX2_TILE_GROUPS_KEY, tile_groups.as_bytes())),
+            ]
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>(),
+        );
+
+        let index_len = Header::LENGTH + LevelIndex::LENGTH * tile_mip_bytes.len();
+        let dfd_offset = index_len;
+        let kvd_offset = dfd_offset + dfd.len();
+
+        // Levels are stored from smallest to largest, each aligned to both the block size and 4.
+        let level_alignment = lcm(block_size as usize, 4);
+        let mut level_offsets = vec![0; tile_mip_bytes.len()];
+        let mut end = kvd_offset + kvd.len();
+        for (offset, &len) in level_offsets.iter_mut().zip(&tile_mip_bytes).rev() {
+            *offset = end.next_multiple_of(level_alignment);
+            end = *offset + len * usize::from(self.tile_count);
+        }
+
+        let header = Header {
+            format: Some(*format),
+            type_size: match samples {
+                Samples::Block(..) => 1,
+                Samples::Unorm(channels) | Samples::Float(channels) => {
+                    block_size / channels.len() as u32
+                }
+            },
+            pixel_width: self.tile_size[0],
+            pixel_height: self.tile_size[1],
+            pixel_depth: 0,
+            layer_count: self.tile_count.into(),
+            face_count: 1,
+            level_count: self.texture_mips,
+            supercompression_scheme: None,
+            index: Index {
+                dfd_byte_offset: dfd_offset as u32,
+                dfd_byte_length: dfd.len() as u32,
+                kvd_byte_offset: kvd_offset as u32,
+                kvd_byte_length: kvd.len() as u32,
+                sgd_byte_offset: 0,
+                sgd_byte_length: 0,
+            },
+        };
+
+        let mut bytes = Vec::with_capacity(end);
+        bytes.extend_from_slice(&header.as_bytes());
+        for (&offset, &len) in level_offsets.iter().zip(&tile_mip_bytes) {
+            let len = (len * usize::from(self.tile_count)) as u64;
+            let level = LevelIndex {
+                byte_offset: offset as u64,
+                byte_length: len,
+                uncompressed_byte_length: len,
+            };
+            bytes.extend_from_slice(&level.as_bytes());
+        }
+        bytes.extend_from_slice(&dfd);
+        bytes.extend_from_slice(&kvd);
+
+        let tile_len = tile_mip_bytes.iter().sum::<usize>();
+        for (level, &offset) in level_offsets.iter().enumerate().rev() {
+            bytes.resize(offset, 0);
+
+            let start = tile_mip_bytes[..level].iter().sum::<usize>();
+            let len = tile_mip_bytes[level];
+            for tile in self.texture_data.chunks_exact(tile_len) {
+                bytes.extend_from_slice(&tile[start..start + len]);
+            }
+        }
+
+        writer.write_all(&bytes)?;
+        Ok(())
+    }
+}
+
+/// Decompresses a single level of a KTX2 file, which should decompress to `len` bytes.
+fn supercompression_decompress(
+    header: Header,
+    data: &[u8],
+    len: usize,
+) -> Result<Vec<u8>, TilesetFileError> {
+    // `len` comes from the file, so it only limits how much is decoded
+    let limit = len as u64 + 1;
+    let mut decompressed = Vec::new();
+    match header.supercompression_scheme {
+        None => return Ok(data.to_vec()),
+        Some(SupercompressionScheme::Zstandard) => {
+            zstd::Decoder::new(data)?
+                .take(limit)
+                .read_to_end(&mut decompressed)?;
+        }
+        Some(SupercompressionScheme::ZLIB) => {
+            flate2::read::ZlibDecoder::new(data)
+                .take(limit)
+                .read_to_end(&mut decompressed)?;
+        }
+        Some(_) => {
+            return Err(TilesetFileError::UnsupportedKtx2(
+                "unsupported supercompression scheme",
+            ));
+        }
+    }
+    Ok(decompressed)
+}
+
+/// Returns `true` if the basic data format descriptor block is flagged as premultiplied.
+fn read_premultiplied_alpha(reader: &Reader<&[u8]>) -> Result<bool, TilesetFileError> {
+    match reader
+        .dfd_blocks()
+        .find(|block| block.header == DfdHeader::BASIC)
+    {
+        Some(block) => Ok(DfdBlockBasic::parse(block.data)?
+            .header
+            .flags
+            .contains(DataFormatFlags::ALPHA_PREMULTIPLIED)),
+        None => Ok(false),
+    }
+}
+
+fn read_tile_groups(reader: &Reader<&[u8]>) -> Result<TileGroupData, TilesetFileError> {
+    match read_text_value(reader, KTX2_TILE_GROUPS_KEY)? {
+        Some(value) => ron::from_str(value).map_err(TilesetFileError::Ktx2TileGroups),
+        None => Ok(Vec::new()),
+    }
+}
+
+fn read_max_array_layers(reader: &Reader<&[u8]>) -> Result<Option<u32>, TilesetFileError> {
+    read_text_value(reader, KTX2_MAX_ARRAY_LAYERS_KEY)?
+        .map(|value| value.parse().map_err(|_| TilesetFileError::InvalidData))
+        .transpose()
+}
+
+/// Reads a text value from the key/value data, if the key is present.
+fn read_text_value<'a>(
+    reader: &'a Reader<&[u8]>,
+    key: &str,
+) -> Result<Option<&'a str>, TilesetFileError> {
+    let Some((_, value)) = reader.key_value_data().find(|(k, _)| *k == key) else {
+        return Ok(None);
+    };
+
+    // Text values are NUL-terminated.
+    let value = value.strip_suffix(&[0]).unwrap_or(value);
+    std::str::from_utf8(value)
+        .map(Some)
+        .map_err(|_| TilesetFileError::InvalidData)
+}
+
+fn tile_groups_value(tile_groups: &TileGroupData) -> String {
+    let mut value = ron::to_string(tile_groups).expect("tile groups are always serializable");
+    value.push('\0');
+    value
+}
+
+/// Encodes a basic data format descriptor, including its leading total size.
+fn data_format_descriptor(
+    format: TextureFormat,
+    block_size: u32,
+    samples: &Samples,
+    premultiplied_alpha: bool,
+) -> Vec<u8> {
+    let srgb = format.is_srgb();
+    let (block_width, block_height) = format.block_dimensions();
+
+    let (color_model, channels, qualifiers, lower, upper) = match samples {
+        Samples::Unorm(channels) => {
+            let bits = block_size * 8 / channels.len() as u32;
+            let upper = if bits >= 32 {
+                u32::MAX
+            } else {
+                (1 << bits) - 1
+            };
+            let qualifiers = ChannelTypeQualifiers::empty();
+            (ColorModel::RGBSDA, *channels, qualifiers, 0, upper)
+        }
+        Samples::Float(channels) => {
+            let qualifiers = ChannelTypeQualifiers::FLOAT | ChannelTypeQualifiers::SIGNED;
+            let (lower, upper) = ((-1.0f32).to_bits(), 1.0f32.to_bits());
+            (ColorModel::RGBSDA, *channels, qualifiers, lower, upper)
+        }
+        Samples::Block(color_model, channels) => {
+            let qualifiers = ChannelTypeQualifiers::empty();
+            (*color_model, *channels, qualifiers, 0, u32::MAX)
+        }
+    };
+
+    let block_dimension = |n: u32| NonZeroU8::new(n as u8).expect("block dimensions are nonzero");
+    let block_header = DfdBlockHeaderBasic {
+        color_model: Some(color_model),
+        color_primaries: Some(ColorPrimaries::BT709),
+        transfer_function: Some(if srgb {
+            TransferFunction::SRGB
+        } else {
+            TransferFunction::Linear
+        }),
+        flags: if premultiplied_alpha {
+            DataFormatFlags::ALPHA_PREMULTIPLIED
+        } else {
+            DataFormatFlags::STRAIGHT_ALPHA
+        },
+        texel_block_dimensions: [
+            block_dimension(block_width),
+            block_dimension(block_height),
+            block_dimension(1),
+            block_dimension(1),
+        ],
+        bytes_planes: [block_size as u8, 0, 0, 0, 0, 0, 0, 0],
+    };
+
+    let sample_bits = block_size * 8 / channels.len() as u32;
+    let block_len = DfdHeader::LENGTH
+        + DfdBlockHeaderBasic::LENGTH
+        + SampleInformation::LENGTH * channels.len();
+
+    let mut dfd = Vec::with_capacity(4 + block_len);
+    dfd.extend_from_slice(&(4 + block_len as u32).to_le_bytes());
+    dfd.extend_from_slice(&DfdHeader::BASIC.as_bytes(block_len as u16));
+    dfd.extend_from_slice(&block_header.as_bytes());
+
+    for (i, &channel) in channels.iter().enumerate() {
+        // The alpha channel of an sRGB format is always linear.
+        let qualifiers = if srgb && channel == A {
+            qualifiers | ChannelTypeQualifiers::LINEAR
+        } else {
+            qualifiers
+        };
+        let sample = SampleInformation {
+            bit_offset: (i as u32 * sample_bits) as u16,
+            bit_length: NonZeroU8::new(sample_bits as u8).expect("samples are nonempty"),
+            channel_type: channel,
+            channel_type_qualifiers: qualifiers,
+            sample_positions: [0; 4],
+            lower,
+            upper,
+        };
+        dfd.extend_from_slice(&sample.as_bytes());
+    }
+
+    dfd
+}
+
+/// Encodes key/value data, which must be sorted by key.
+fn key_value_data(entries: &[(&str, &[u8])]) -> Vec<u8> {
+    let mut kvd = Vec::new();
+    for (key, value) in entries {
+        let len = key.len() + 1 + value.len();
+        kvd.extend_from_slice(&(len as u32).to_le_bytes());
+        kvd.extend_from_slice(key.as_bytes());
+        kvd.push(0);
+        kvd.extend_from_slice(value);
+        kvd.resize(kvd.len().next_multiple_of(4), 0);
+    }
+    kvd
+}
+
+fn lcm(a: usize, b: usize) -> usize {
+    let gcd = |mut a: usize, mut b: usize| {
+        while b != 0 {
+            (a, b) = (b, a % b);
+        }
+        a
+    };
+    a / gcd(a, b) * b
+}
@@ -0,0 +1,814 @@
+use std::io::{self, Read, Seek, Write};
+
+use bevy_asset::Asset;
+use bevy_image::{Image, TextureAtlasLayout};
+use bevy_log::warn;
+use bevy_math::{URect, UVec2};
+use bevy_reflect::TypePath;
+use bincode::{Decode, Encode};
+use thiserror::Error;
+use wgpu_types::{
+    Extent3d, TextureDataOrder, TextureDimension, TextureFormat, TextureViewDescriptor,
+    TextureViewDimension,
+};
+
+use crate::{TileGroups, TileIndex, parallel::par_map};
+
+mod atlas;
+mod chunked;
+mod compression;
+mod header;
+mod info;
+mod ktx2;
+
+pub use atlas::OutputLayout;
+pub use chunked::ChunkedTilesetReader;
+pub use compression::*;
+pub use header::*;
+pub use info::{TilesetFileInfo, TilesetFileLayerInfo};
+pub use ktx2::{KTX2_MAX_ARRAY_LAYERS_KEY, KTX2_TILE_GROUPS_KEY};
+
+use atlas::AtlasGrid;
+use chunked::{ChunkTable, chunk_ranges, decompress_chunks};
+
+type TileGroupData = Vec<(String, Vec<TileIndex>)>;
+
+/// A tileset file format that is tightly coupled to a bevy [`Image`] for efficient loading.
+///
+/// The byte format of a tileset file should be considered only semi-stable between bevy
+/// versions (as [`Image`] itself is not guaranteed to be stable), and re-importing tilesets
+/// may be a required migration step when upgrading. Each file begins with a
+/// [`TilesetFileHeader`] recording the versions it was written against, so that stale files are
+/// reported as [`TilesetFileError::Stale`] rather than failing to decode.
+#[derive(Asset, TypePath, Debug, PartialEq, Encode, Decode)]
+pub struct TilesetFile {
+    pub tile_size: [u32; 2],
+    pub tile_count: TileIndex,
+    pub tile_groups: TileGroupData,
+    #[bincode(with_serde)]
+    pub texture_format: TextureFormat,
+    pub texture_mips: u32,
+    pub texture_data: Vec<u8>,
+    /// Whether the color channels of the texture are premultiplied by alpha. This is stored in
+    /// the [`TilesetFileHeader`].
+    pub premultiplied_alpha: bool,
+    /// Companion textures with the same tiles as the main texture, such as normal maps.
+    pub layers: Vec<TilesetFileLayer>,
+    /// How the tiles are arranged in the loaded textures.
+    pub output_layout: OutputLayout,
+    /// If set, the loaded textures are split into pages of at most this many array layers. See
+    /// [`TilesetImportSettings::max_array_layers`](crate::TilesetImportSettings::max_array_layers).
+    pub max_array_layers: Option<u32>,
+    /// How the frame of each tile was trimmed, indexed by [`TileIndex`]. This is empty unless
+    /// the tileset was imported with [`TilesetImportSettings::trim`](crate::TilesetImportSettings::trim).
+    pub tile_trims: Vec<TileTrim>,
+}
+
+/// How the frame of a tile was trimmed to its visible pixels during import.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Encode, Decode)]
+pub struct TileTrim {
+    /// The offset of the trimmed frame from the top left of the untrimmed frame, in the source
+    /// image.
+    #[bincode(with_serde)]
+    pub offset: UVec2,
+    /// The size of the untrimmed frame.
+    #[bincode(with_serde)]
+    pub original_size: UVec2,
+    /// The rect covered by the trimmed frame within the tile, after the tile transform. This is
+    /// empty if no pixel of the frame is visible, in which case it is not trimmed.
+    #[bincode(with_serde)]
+    pub rect: URect,
+}
+
+/// A companion texture in a [`TilesetFile`], with the same tile size, tile count, and mip levels
+/// as the main texture.
+#[derive(Debug, Clone, PartialEq, Encode, Decode)]
+pub struct TilesetFileLayer {
+    pub name: String,
+    #[bincode(with_serde)]
+    pub texture_format: TextureFormat,
+    pub texture_data: Vec<u8>,
+}
+
+/// Everything in a [`TilesetFile`] except for the texture data.
+///
+/// This is stored uncompressed ahead of the texture data, prefixed by its length as a
+/// little-endian `u32`.
+#[derive(Debug, Clone, Encode, Decode)]
+struct TilesetFileMeta {
+    tile_size: [u32; 2],
+    tile_count: TileIndex,
+    tile_groups: TileGroupData,
+    #[bincode(with_serde)]
+    texture_format: TextureFormat,
+    texture_mips: u32,
+    layers: Vec<TilesetLayerMeta>,
+    output_layout: OutputLayout,
+    max_array_layers: Option<u32>,
+    tile_trims: Vec<TileTrim>,
+}
+
+/// The metadata of a [`TilesetFileLayer`]. Its texture data is stored after the main texture
+/// data, compressed as a single block with the same length prefix as [`Storage::Contiguous`].
+#[derive(Debug, Clone, Encode, Decode)]
+struct TilesetLayerMeta {
+    name: String,
+    #[bincode(with_serde)]
+    texture_format: TextureFormat,
+}
+
+/// The metadata of an unversioned (version 0) file, which was encoded together with the texture
+/// data.
+#[derive(Debug, Clone, Decode)]
+struct UnversionedFileMeta {
+    tile_size: [u32; 2],
+    tile_count: TileIndex,
+    tile_groups: TileGroupData,
+    #[bincode(with_serde)]
+    texture_format: TextureFormat,
+    texture_mips: u32,
+}
+
+impl From<UnversionedFileMeta> for TilesetFileMeta {
+    fn from(meta: UnversionedFileMeta) -> Self {
+        let UnversionedFileMeta {
+            tile_size,
+            tile_count,
+            tile_groups,
+            texture_format,
+            texture_mips,
+        } = meta;
+
+        Self {
+            tile_size,
+            tile_count,
+            tile_groups,
+            texture_format,
+            texture_mips,
+            layers: Vec::new(),
+            output_layout: OutputLayout::Array,
+            max_array_layers: None,
+            tile_trims: Vec::new(),
+        }
+    }
+}
+
+impl TilesetFileMeta {
+    /// Reads the length-prefixed metadata.
+    fn read(header: &TilesetFileHeader, mut reader: impl Read) -> Result<Self, TilesetFileError> {
+        let mut len = [0; 4];
+        reader.read_exact(&mut len)?;
+        let bytes = read_exact_vec(reader, u32::from_le_bytes(len).into())?;
+
+        bincode::decode_from_slice(&bytes, bincode::config::standard())
+            .map(|(meta, _)| meta)
+            .map_err(|err| decode_error(header, err))
+    }
+
+    /// Writes the length-prefixed metadata.
+    fn write(&self, mut writer: impl Write) -> Result<(), TilesetFileError> {
+        let bytes = bincode::encode_to_vec(self, bincode::config::standard())?;
+        let len = u32::try_from(bytes.len()).map_err(|_| TilesetFileError::InvalidData)?;
+
+        writer.write_all(&len.to_le_bytes())?;
+        writer.write_all(&bytes)?;
+        Ok(())
+    }
+
+    /// Returns the number of bytes in a single tile at each mip level.
+    fn tile_mip_bytes(&self) -> Result<Vec<usize>, TilesetFileError> {
+        self.format_tile_mip_bytes(self.texture_format)
+    }
+
+    /// Returns the number of bytes in a single tile at each mip level, for a texture with the
+    /// same tiles in another format.
+    fn format_tile_mip_bytes(
+        &self,
+        texture_format: TextureFormat,
+    ) -> Result<Vec<usize>, TilesetFileError> {
+        let tile_extent = Extent3d {
+            width: self.tile_size[0],
+            height: self.tile_size[1],
+            depth_or_array_layers: 1,
+        };
+
+        (0..self.texture_mips)
+            .map(|m| {
+                mip_level_bytes(
+                    texture_format,
+                    tile_extent.mip_level_size(m, TextureDimension::D2),
+                )
+                .ok_or(TilesetFileError::InvalidData)
+            })
+            .collect()
+    }
+
+    /// Returns the total number of bytes in the uncompressed texture data.
+    fn texture_len(&self) -> Result<usize, TilesetFileError> {
+        self.format_texture_len(self.texture_format)
+    }
+
+    /// Returns the total number of bytes in the uncompressed texture data of a layer.
+    fn format_texture_len(&self, texture_format: TextureFormat) -> Result<usize, TilesetFileError> {
+        Ok(self
+            .format_tile_mip_bytes(texture_format)?
+            .iter()
+            .sum::<usize>()
+            * usize::from(self.tile_count))
+    }
+
+    /// Returns the number of chunks in a file written with [`Storage::Chunked`].
+    fn chunk_count(&self) -> usize {
+        usize::from(self.tile_count) * self.texture_mips as usize
+    }
+}
+
+/// Errors encountered when working with [`TilesetFile`].
+#[derive(Debug, Error)]
+pub enum TilesetFileError {
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    /// Returned when attempting to construct a tileset file using an image with uninitialized
+    /// data.
+    #[error("the tileset texture data must be uninitialized")]
+    Uninitialized,
+    #[error("the tileset texture data, format, and size are not in agreement")]
+    InvalidData,
+    /// Returned when attempting to construct or load a tileset file containing more than
+    /// [`TileIndex::MAX`] tiles.
+    #[error("the tileset texture contains {0} tiles, but the maximum tile index is {max}", max=TileIndex::MAX)]
+    TooManyTiles(u32),
+    /// Returned when attempting to encode a tileset file into bytes.
+    #[error("failed to encode tileset data: {0}")]
+    Encode(#[from] bincode::error::EncodeError),
+    /// Returned when attempting to decode a tileset file from bytes.
+    #[error("failed to decode tileset data: {0}")]
+    Decode(#[from] bincode::error::DecodeError),
+    /// Returned when the data does not begin with the tileset file [`MAGIC`] bytes.
+    #[error("not a tileset file")]
+    NotATilesetFile,
+    /// Returned when reading a file written by a newer version of the tileset file format.
+    #[error("tileset file format version {0} is not supported (the latest is {FORMAT_VERSION})")]
+    UnsupportedVersion(u16),
+    /// Returned when the header specifies an unknown compression scheme.
+    #[error("unknown tileset file compression scheme {0}")]
+    UnknownCompression(u8),
+    /// Returned when the header specifies an unknown storage layout.
+    #[error("unknown tileset file storage layout {0}")]
+    UnknownStorage(u8),
+    /// Returned when attempting random access into a file that was not written with
+    /// [`Storage::Chunked`].
+    #[error("the tileset file does not use chunked storage")]
+    NotChunked,
+    #[error("tile index was {tile_index}, but the tileset file contains {tile_count} tiles")]
+    TileOutOfRange {
+        tile_index: TileIndex,
+        tile_count: TileIndex,
+    },
+    #[error("mip level was {level}, but the tileset file contains {mip_count} mip levels")]
+    MipOutOfRange { level: u32, mip_count: u32 },
+    /// Returned when reading a KTX2 file that could not be parsed.
+    #[error("invalid KTX2 file: {0}")]
+    Ktx2(#[from] ::ktx2::ParseError),
+    /// Returned when reading a valid KTX2 file that cannot be represented as a tileset.
+    #[error("unsupported KTX2 file: {0}")]
+    UnsupportedKtx2(&'static str),
+    /// Returned when writing a tileset whose texture format has no KTX2 equivalent.
+    #[error("texture format {0:?} cannot be written to a KTX2 file")]
+    UnsupportedKtx2Format(TextureFormat),
+    /// Returned when writing a tileset with companion layers to a KTX2 file, which can only
+    /// hold a single texture.
+    #[error("tilesets with companion layers cannot be written to a KTX2 file")]
+    Ktx2Layers,
+    /// Returned when writing a tileset with [`OutputLayout::Atlas`] to a KTX2 file, which only
+    /// holds array textures.
+    #[error("tilesets with an atlas output layout cannot be written to a KTX2 file")]
+    Ktx2Atlas,
+    /// Returned when writing a tileset with [`TileTrim`] metadata to a KTX2 file.
+    #[error("tilesets with trimmed tiles cannot be written to a KTX2 file")]
+    Ktx2TileTrims,
+    /// Returned when loading a tileset with [`OutputLayout::Atlas`] and a block-compressed
+    /// texture format.
+    #[error("texture format {0:?} is block-compressed, and cannot be packed into an atlas")]
+    CompressedAtlas(TextureFormat),
+    /// Returned when the tile groups stored in a KTX2 file could not be parsed.
+    #[error("invalid tile groups in KTX2 file: {0}")]
+    Ktx2TileGroups(#[source] ron::error::SpannedError),
+    /// Returned when a file written by an older format version, or against different bevy or
+    /// `wgpu-types` versions, can no longer be decoded. The source asset must be re-imported.
+    #[error(
+        "tileset file (format version {}, bevy {}.{}, wgpu-types {}) could not be decoded: {err}",
+        header.version,
+        header.bevy_version[0],
+        header.bevy_version[1],
+        header.wgpu_types_version
+    )]
+    Stale {
+        header: TilesetFileHeader,
+        #[source]
+        err: bincode::error::DecodeError,
+    },
+}
+
+impl TilesetFileError {
+    /// Returns `true` if this error indicates that the file must be re-imported.
+    ///
+    /// Files from a newer format version are not stale, since re-importing them with this
+    /// version would not help. See [`TilesetFileError::is_newer_version`].
+    pub fn is_stale(&self) -> bool {
+        matches!(self, Self::Stale { .. })
+    }
+
+    /// Returns `true` if the file was written by a newer version of the tileset file format, and
+    /// can only be loaded by updating this crate.
+    pub fn is_newer_version(&self) -> bool {
+        matches!(self, Self::UnsupportedVersion(_))
+    }
+}
+
+impl TilesetFile {
+    pub fn new(tile_groups: TileGroups, texture: Image) -> Result<Self, TilesetFileError> {
+        let descriptor = &texture.texture_descriptor;
+
+        let texture_format = descriptor.format;
+        let texture_size = descriptor.size;
+        let texture_mips = descriptor.mip_level_count;
+        let texture_data = texture.data.ok_or(TilesetFileError::Uninitialized)?;
+
+        validate_data_volume(texture_format, texture_size, texture_mips, &texture_data)?;
+
+        Ok(Self {
+            tile_size: [texture_size.width, texture_size.height],
+            tile_count: texture_size
+                .depth_or_array_layers
+                .try_into()
+                .map_err(|_| TilesetFileError::TooManyTiles(texture_size.depth_or_array_layers))?,
+            tile_groups: tile_groups.into_file_data(),
+            texture_format,
+            texture_mips,
+            texture_data,
+            premultiplied_alpha: false,
+            layers: Vec::new(),
+            output_layout: OutputLayout::Array,
+            max_array_layers: None,
+            tile_trims: Vec::new(),
+        })
+    }
+
+    /// Returns the rect of each tile in the loaded textures, if the tiles are packed into an
+    /// atlas by [`OutputLayout::Atlas`].
+    pub fn atlas_layout(&self) -> Option<TextureAtlasLayout> {
+        match self.output_layout {
+            OutputLayout::Array => None,
+            OutputLayout::Atlas { columns, padding } => Some(
+                AtlasGrid::new(self.tile_size, self.tile_count, columns, padding)
+                    .layout(self.tile_count),
+            ),
+        }
+    }
+
+    /// Removes the companion layers, returning each as a named image in the same form as
+    /// [`TilesetFile::into_count_groups_image`].
+    pub fn take_layer_images(&mut self) -> Result<Vec<(String, Image)>, TilesetFileError> {
+        std::mem::take(&mut self.layers)
+            .into_iter()
+            .map(|layer| {
+                let image = tileset_image(
+                    self.tile_size,
+                    self.tile_count,
+                    layer.texture_format,
+                    self.texture_mips,
+                    layer.texture_data,
+                    self.output_layout,
+                )?;
+                Ok((layer.name, image))
+            })
+            .collect()
+    }
+
+    pub fn into_count_groups_image(
+        self,
+    ) -> Result<(TileIndex, TileGroups, Image), TilesetFileError> {
+        let TilesetFile {
+            tile_size,
+            tile_count,
+            tile_groups,
+            texture_format,
+            texture_mips,
+            texture_data,
+            premultiplied_alpha: _,
+            layers: _,
+            output_layout,
+            max_array_layers: _,
+            tile_trims: _,
+        } = self;
+
+        let image = tileset_image(
+            tile_size,
+            tile_count,
+            texture_format,
+            texture_mips,
+            texture_data,
+            output_layout,
+        )?;
+
+        Ok((tile_count, TileGroups::from_file_data(tile_groups), image))
+    }
+
+    /// Reads a tileset file, including its header.
+    ///
+    /// Files in the unversioned (version 0) format are migrated if they can still be decoded.
+    pub fn read(bytes: impl Read) -> Result<Self, TilesetFileError> {
+        let (header, mut bytes) = read_any_header(bytes)?;
+        if header.version == 0 {
+            return Self::read_legacy(&header, bytes);
+        }
+
+        let mut meta = TilesetFileMeta::read(&header, &mut bytes)?;
+        let tile_mip_bytes = meta.tile_mip_bytes()?;
+
+        let texture_data = match header.storage {
+            Storage::Contiguous => {
+                let mut len = [0; 8];
+                bytes.read_exact(&mut len)?;
+                let data = read_exact_vec(&mut bytes, u64::from_le_bytes(len))?;
+
+                header.compression.decompress(&data, meta.texture_len()?)?
+            }
+            Storage::Chunked => {
+                let table = ChunkTable::read(&mut bytes, meta.chunk_count())?;
+                let data = read_exact_vec(&mut bytes, table.data_len())?;
+
+                decompress_chunks(header.compression, &table.split(&data, &tile_mip_bytes))?
+            }
+        };
+
+        let layers = std::mem::take(&mut meta.layers)
+            .into_iter()
+            .map(
+                |TilesetLayerMeta {
+                     name,
+                     texture_format,
+                 }| {
+                    let mut len = [0; 8];
+                    bytes.read_exact(&mut len)?;
+                    let data = read_exact_vec(&mut bytes, u64::from_le_bytes(len))?;
+
+                    Ok(TilesetFileLayer {
+                        name,
+                        texture_format,
+                        texture_data: header
+                            .compression
+                            .decompress(&data, meta.format_texture_len(texture_format)?)?,
+                    })
+                },
+            )
+            .collect::<Result<_, TilesetFileError>>()?;
+
+        Ok(Self {
+            premultiplied_alpha: header.premultiplied_alpha,
+            layers,
+            ..Self::from_meta(meta, texture_data)
+        })
+    }
+
+    /// Reads every mip level of a single tile from a file written with [`Storage::Chunked`].
+    ///
+    /// To read multiple tiles from the same file, use [`ChunkedTilesetReader`] instead.
+    pub fn read_tile(
+        reader: impl Read + Seek,
+        tile_index: TileIndex,
+    ) -> Result<Vec<u8>, TilesetFileError> {
+        ChunkedTilesetReader::new(reader)?.read_tile(tile_index)
+    }
+
+    /// Reads a single mip level of every tile from a file written with [`Storage::Chunked`].
+    ///
+    /// To read multiple mip levels from the same file, use [`ChunkedTilesetReader`] instead.
+    pub fn read_mip(reader: impl Read + Seek, level: u32) -> Result<Vec<u8>, TilesetFileError> {
+        ChunkedTilesetReader::new(reader)?.read_mip(level)
+    }
+
+    /// Decodes the contents of an unversioned (version 0) file, which are compressed as a whole.
+    fn read_legacy(header: &TilesetFileHeader, bytes: impl Read) -> Result<Self, TilesetFileError> {
+        let mut decoder = header.compression.decoder(bytes)?;
+
+        // The metadata and texture data were encoded together as a single struct.
+        let (meta, texture_data): (UnversionedFileMeta, _) =
+            bincode::decode_from_std_read(&mut decoder, bincode::config::standard())
+                .map_err(|err| decode_error(header, err))?;
+
+        warn!("migrated an unversioned tileset file; it should be re-imported");
+        Ok(Self::from_meta(meta.into(), texture_data))
+    }
+
+    /// Writes the tileset file, including its header.
+    pub fn write(
+        &self,
+        compression: Compression,
+        storage: Storage,
+        mut writer: impl Write,
+    ) -> Result<(), TilesetFileError> {
+        let meta = self.meta();
+        if meta.texture_len()? != self.texture_data.len() {
+            return Err(TilesetFileError::InvalidData);
+        }
+        for layer in &self.layers {
+            if meta.format_texture_len(layer.texture_format)? != layer.texture_data.len() {
+                return Err(TilesetFileError::InvalidData);
+            }
+        }
+        if !self.tile_trims.is_empty() && self.tile_trims.len() != usize::from(self.tile_count) {
+            return Err(TilesetFileError::InvalidData);
+        }
+
+        TilesetFileHeader {
+            premultiplied_alpha: self.premultiplied_alpha,
+            ..TilesetFileHeader::current(compression.scheme(), storage)
+        }
+        .write(&mut writer)?;
+        meta.write(&mut writer)?;
+
+        match storage {
+            Storage::Contiguous => {
+                let data = compression.compress(&self.texture_data)?;
+                writer.write_all(&(data.len() as u64).to_le_bytes())?;
+                writer.write_all(&data)?;
+            }
+            Storage::Chunked => {
+                let ranges = chunk_ranges(&meta.tile_mip_bytes()?, self.tile_count);
+                let chunks = par_map(&ranges, |range| {
+                    compression.compress(&self.texture_data[range.clone()])
+                })
+                .into_iter()
+                .collect::<io::Result<Vec<_>>>()?;
+
+                ChunkTable::write(&chunks, &mut writer)?;
+                for chunk in &chunks {
+                    writer.write_all(chunk)?;
+                }
+            }
+        }
+
+        for layer in &self.layers {
+            let data = compression.compress(&layer.texture_data)?;
+            writer.write_all(&(data.len() as u64).to_le_bytes())?;
+            writer.write_all(&data)?;
+        }
+
+        Ok(())
+    }
+
+    fn meta(&self) -> TilesetFileMeta {
+        TilesetFileMeta {
+            tile_size: self.tile_size,
+            tile_count: self.tile_count,
+            tile_groups: self.tile_groups.clone(),
+            texture_format: self.texture_format,
+            texture_mips: self.texture_mips,
+            layers: self
+                .layers
+                .iter()
+                .map(|layer| TilesetLayerMeta {
+                    name: layer.name.clone(),
+                    texture_format: layer.texture_format,
+                })
+                .collect(),
+            output_layout: self.output_layout,
+            max_array_layers: self.max_array_layers,
+            tile_trims: self.tile_trims.clone(),
+        }
+    }
+
+    /// Creates a file from its metadata and main texture data, without any layers.
+    fn from_meta(meta: TilesetFileMeta, texture_data: Vec<u8>) -> Self {
+        let TilesetFileMeta {
+            tile_size,
+            tile_count,
+            tile_groups,
+            texture_format,
+            texture_mips,
+            layers: _,
+            output_layout,
+            max_array_layers,
+            tile_trims,
+        } = meta;
+
+        Self {
+            tile_size,
+            tile_count,
+            tile_groups,
+            texture_format,
+            texture_mips,
+            texture_data,
+            premultiplied_alpha: false,
+            layers: Vec::new(),
+            output_layout,
+            max_array_layers,
+            tile_trims,
+        }
+    }
+}
+
+/// Creates an image from tileset texture data, arranged according to `output_layout`.
+fn tileset_image(
+    tile_size: [u32; 2],
+    tile_count: TileIndex,
+    texture_format: TextureFormat,
+    texture_mips: u32,
+    texture_data: Vec<u8>,
+    output_layout: OutputLayout,
+) -> Result<Image, TilesetFileError> {
+    if let OutputLayout::Atlas { columns, padding } = output_layout {
+        return AtlasGrid::new(tile_size, tile_count, columns, padding).pack(
+            tile_count,
+            texture_format,
+            texture_mips,
+            &texture_data,
+        );
+    }
+
+    let texture_size = Extent3d {
+        width: tile_size[0],
+        height: tile_size[1],
+        depth_or_array_layers: tile_count.into(),
+    };
+
+    validate_data_volume(texture_format, texture_size, texture_mips, &texture_data)?;
+
+    // `Image::new` expects data for the base level only
+    let mut image = Image::new_uninit(
+        texture_size,
+        TextureDimension::D2,
+        texture_format,
+        Default::default(),
+    );
+
+    image.data = Some(texture_data);
+    image.data_order = TextureDataOrder::LayerMajor;
+    image.texture_descriptor.mip_level_count = texture_mips;
+
+    Ok(image)
+}
+
+/// Splits a 2D array image, such as one returned by [`TilesetFile::into_count_groups_image`],
+/// into pages of at most `max_layers` consecutive array layers each.
+///
+/// Each page is viewed as a 2D array, even if it contains a single layer. The image is returned
+/// as the only page if it already fits.
+pub fn split_array_pages(
+    mut image: Image,
+    max_layers: u32,
+) -> Result<Vec<Image>, TilesetFileError> {
+    let max_layers = max_layers.max(1);
+    let layers = image.texture_descriptor.size.depth_or_array_layers;
+    if layers <= max_layers {
+        return Ok(vec![image]);
+    }
+
+    let data = image.data.take().ok_or(TilesetFileError::Uninitialized)?;
+    if image.data_order != TextureDataOrder::LayerMajor || data.len() % layers as usize != 0 {
+        return Err(TilesetFileError::InvalidData);
+    }
+    image.texture_view_descriptor = Some(TextureViewDescriptor {
+        dimension: Some(TextureViewDimension::D2Array),
+        ..Default::default()
+    });
+
+    // Every mip level of a layer is contiguous, so each page is a contiguous range of the data
+    let layer_bytes = data.len() / layers as usize;
+    Ok(data
+        .chunks(layer_bytes * max_layers as usize)
+        .map(|page_data| {
+            let mut page = image.clone();
+            page.texture_descriptor.size.depth_or_array_layers =
+                (page_data.len() / layer_bytes) as u32;
+            page.data = Some(page_data.to_vec());
+            page
+        })
+        .collect())
+}
+
+/// A reader over the file contents following the header. For the unversioned format, this must
+/// include the bytes that were read while checking for the [`MAGIC`] bytes.
+type ContentsReader<R> = io::Chain<io::Cursor<[u8; 3]>, R>;
+
+/// Reads the header of a file in any supported format version, returning it along with a reader
+/// positioned at the start of the file contents.
+fn read_any_header<R: Read>(
+    mut bytes: R,
+) -> Result<(TilesetFileHeader, ContentsReader<R>), TilesetFileError> {
+    let mut magic = [0; MAGIC.len()];
+    bytes.read_exact(&mut magic)?;
+
+    let mut rest = io::Cursor::new([magic[1], magic[2], magic[3]]);
+
+    let header = if magic == MAGIC {
+        rest.set_position(3);
+        TilesetFileHeader::read_after_magic(&mut bytes)?
+    } else {
+        // The unversioned format starts with a single compression flag byte, so the rest of
+        // the "magic" bytes are part of the file contents.
+        let compression = match magic[0] {
+            0 => CompressionScheme::None,
+            1 => CompressionScheme::Deflate,
+            _ => return Err(TilesetFileError::NotATilesetFile),
+        };
+        TilesetFileHeader {
+            version: 0,
+            bevy_version: [0, 0],
+            wgpu_types_version: 0,
+            compression,
+            storage: Storage::Contiguous,
+            premultiplied_alpha: false,
+        }
+    };
+
+    Ok((header, rest.chain(bytes)))
+}
+
+/// Converts a decoding error into [`TilesetFileError::Stale`] if it may have been caused by a
+/// mismatch between the writer and reader versions.
+fn decode_error(header: &TilesetFileHeader, err: bincode::error::DecodeError) -> TilesetFileError {
+    match err {
+        // I/O errors are not a sign of an incompatible encoding.
+        err @ bincode::error::DecodeError::Io { .. } => err.into(),
+        // Anything else is the result of a version mismatch, or of corrupted data.
+        err if !header.is_current() => TilesetFileError::Stale {
+            header: *header,
+            err,
+        },
+        err => err.into(),
+    }
+}
+
+/// Reads exactly `len` bytes into a new buffer, without trusting `len` for the allocation size.
+fn read_exact_vec(reader: impl Read, len: u64) -> io::Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    reader.take(len).read_to_end(&mut bytes)?;
+
+    if bytes.len() as u64 != len {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+    Ok(bytes)
+}
+
+impl TileGroups {
+    fn from_file_data(data: TileGroupData) -> Self {
+        let mut indices = Vec::new();
+        let ranges = data
+            .into_iter()
+            .map(|(name, mut group_indices)| {
+                let i = indices.len();
+                indices.append(&mut group_indices);
+                (name, i..indices.len())
+            })
+            .collect();
+        Self { ranges, indices }
+    }
+
+    fn into_file_data(self) -> TileGroupData {
+        self.ranges
+            .into_iter()
+            .map(|(name, range)| (name, self.indices[range].to_vec()))
+            .collect()
+    }
+}
+
+/// Checks that `texture_data` contains the expected number of bytes for a texture with the
+/// specified format, size, and mip levels.
+fn validate_data_volume(
+    texture_format: TextureFormat,
+    texture_size: Extent3d,
+    texture_mips: u32,
+    texture_data: &[u8],
+) -> Result<(), TilesetFileError> {
+    let n_bytes = (0..texture_mips)
+        .map(|m| {
+            mip_level_bytes(
+                texture_format,
+                texture_size.mip_level_size(m, TextureDimension::D2),
+            )
+        })
+        .sum::<Option<usize>>();
+
+    if n_bytes == Some(texture_data.len()) {
+        Ok(())
+    } else {
+        Err(TilesetFileError::InvalidData)
+    }
+}
+
+/// Returns the number of bytes in a mip level with the given size, accounting for block-compressed
+/// formats. Returns `None` if the format has no well-defined size, such as for combined
+/// depth-stencil formats.
+fn mip_level_bytes(texture_format: TextureFormat, mip_size: Extent3d) -> Option<usize> {
+    let (block_width, block_height) = texture_format.block_dimensions();
+    let block_size = texture_format.block_copy_size(None)?;
+
+    let blocks = mip_size.width.div_ceil(block_width) as usize
+        * mip_size.height.div_ceil(block_height) as usize
+        * mip_size.depth_or_array_layers as usize;
+
+    Some(blocks * block_size as usize)
+}
@@ -0,0 +1,222 @@
+//! A small BC7 encoder that only emits mode 6 blocks.
+//!
+//! Mode 6 stores a single pair of RGBA endpoints with 4-bit indices, which handles color and
+//! alpha together and gives reasonable quality for most tiles. Multi-subset modes are not
+//! attempted.
+
+/// The interpolation weights for 4-bit indices, out of 64.
+const WEIGHTS: [u32; 16] = [0, 4, 9, 13, 17, 21, 26, 30, 34, 38, 43, 47, 51, 55, 60, 64];
+
+/// A pair of quantized endpoints: 7 bits per channel, plus a shared low bit for each endpoint.
+#[derive(Clone, Copy)]
+struct Endpoints {
+    colors: [[u8; 4]; 2],
+    p_bits: [u8; 2],
+}
+
+impl Endpoints {
+    fn quantize(e0: [f32; 4], e1: [f32; 4]) -> Self {
+        let (c0, p0) = quantize_endpoint(e0);
+        let (c1, p1) = quantize_endpoint(e1);
+        Self {
+            colors: [c0, c1],
+            p_bits: [p0, p1],
+        }
+    }
+
+    /// Returns the 16 colors that can be selected by the block indices.
+    fn palette(&self) -> [[u32; 4]; 16] {
+        let e0 = unquantize_endpoint(self.colors[0], self.p_bits[0]);
+        let e1 = unquantize_endpoint(self.colors[1], self.p_bits[1]);
+
+        WEIGHTS.map(|w| std::array::from_fn(|c| ((64 - w) * e0[c] + w * e1[c] + 32) >> 6))
+    }
+
+    /// Selects the closest palette entry for each pixel, returning the indices and the total
+    /// squared error.
+    fn select_indices(&self, pixels: &[[u8; 4]; 16]) -> ([u8; 16], u32) {
+        let palette = self.palette();
+        let mut indices = [0; 16];
+        let mut total_error = 0;
+
+        for (index, pixel) in indices.iter_mut().zip(pixels) {
+            let (best, error) = palette
+                .iter()
+                .map(|color| {
+                    (0..4)
+                        .map(|c| {
+                            let d = color[c] as i32 - i32::from(pixel[c]);
+                            (d * d) as u32
+                        })
+                        .sum::<u32>()
+                })
+                .enumerate()
+                .min_by_key(|&(_, error)| error)
+                .expect("palette is not empty");
+
+            *index = best as u8;
+            total_error += error;
+        }
+
+        (indices, total_error)
+    }
+}
+
+/// Encodes a 4x4 block of RGBA8 pixels, in row-major order, as a BC7 mode 6 block.
+pub(crate) fn encode_block(pixels: &[[u8; 4]; 16]) -> [u8; 16] {
+    let (e0, e1) = principal_endpoints(pixels);
+    let mut endpoints = Endpoints::quantize(e0, e1);
+    let (mut indices, error) = endpoints.select_indices(pixels);
+
+    // Refine the endpoints using the selected indices.
+    if error > 0
+        && let Some((e0, e1)) = fit_endpoints(pixels, &indices)
+    {
+        let refined = Endpoints::quantize(e0, e1);
+        let (refined_indices, refined_error) = refined.select_indices(pixels);
+        if refined_error < error {
+            endpoints = refined;
+            indices = refined_indices;
+        }
+    }
+
+    // The high bit of the first index is implicitly zero, so swap the endpoints if necessary.
+    if indices[0] >= 8 {
+        endpoints.colors.swap(0, 1);
+        endpoints.p_bits.swap(0, 1);
+        for index in &mut indices {
+            *index = 15 - *index;
+        }
+    }
+
+    pack_block(&endpoints, &indices)
+}
+
+/// Finds initial endpoints by projecting the pixels onto their principal axis.
+fn principal_endpoints(pixels: &[[u8; 4]; 16]) -> ([f32; 4], [f32; 4]) {
+    let pixels = pixels.map(|p| p.map(f32::from));
+
+    let mut mean = [0.0; 4];
+    for pixel in &pixels {
+        for c in 0..4 {
+            mean[c] += pixel[c] / 16.0;
+        }
+    }
+
+    let mut covariance = [[0.0f32; 4]; 4];
+    for pixel in &pixels {
+        for i in 0..4 {
+            for j in 0..4 {
+                covariance[i][j] += (pixel[i] - mean[i]) * (pixel[j] - mean[j]);
+            }
+        }
+    }
+
+    // Approximate the principal eigenvector with a few rounds of power iteration.
+    let mut axis = [1.0f32; 4];
+    for _ in 0..8 {
+        let next: [f32; 4] =
+            std::array::from_fn(|i| (0..4).map(|j| covariance[i][j] * axis[j]).sum());
+        let len = next.iter().map(|v| v * v).sum::<f32>().sqrt();
+        if len < 1e-6 {
+            // All pixels are (nearly) the same color.
+            return (mean, mean);
+        }
+        axis = next.map(|v| v / len);
+    }
+
+    let project = |pixel: &[f32; 4]| (0..4).map(|c| (pixel[c] - mean[c]) * axis[c]).sum::<f32>();
+    let (min, max) = pixels
+        .iter()
+        .map(project)
+        .fold((f32::MAX, f32::MIN), |(min, max), t| {
+            (min.min(t), max.max(t))
+        });
+
+    (
+        std::array::from_fn(|c| mean[c] + axis[c] * min),
+        std::array::from_fn(|c| mean[c] + axis[c] * max),
+    )
+}
+
+/// Finds the endpoints that minimize the squared error for the given indices.
+fn fit_endpoints(pixels: &[[u8; 4]; 16], indices: &[u8; 16]) -> Option<([f32; 4], [f32; 4])> {
+    let mut aa = 0.0;
+    let mut bb = 0.0;
+    let mut ab = 0.0;
+    let mut ax = [0.0f32; 4];
+    let mut bx = [0.0f32; 4];
+
+    for (pixel, &index) in pixels.iter().zip(indices) {
+        let b = WEIGHTS[usize::from(index)] as f32 / 64.0;
+        let a = 1.0 - b;
+
+        aa += a * a;
+        bb += b * b;
+        ab += a * b;
+        for c in 0..4 {
+            ax[c] += a * f32::from(pixel[c]);
+            bx[c] += b * f32::from(pixel[c]);
+        }
+    }
+
+    let det = aa * bb - ab * ab;
+    if det.abs() < 1e-6 {
+        return None;
+    }
+
+    Some((
+        std::array::from_fn(|c| (ax[c] * bb - bx[c] * ab) / det),
+        std::array::from_fn(|c| (bx[c] * aa - ax[c] * ab) / det),
+    ))
+}
+
+/// Quantizes an endpoint to 7 bits per channel, choosing the shared low bit that gives the
+/// smallest error.
+fn quantize_endpoint(color: [f32; 4]) -> ([u8; 4], u8) {
+    (0..2u8)
+        .map(|p| {
+            let quantized = color.map(|v| {
+                ((v.clamp(0.0, 255.0) - f32::from(p)) / 2.0)
+                    .round()
+                    .clamp(0.0, 127.0) as u8
+            });
+            let error = unquantize_endpoint(quantized, p)
+                .iter()
+                .zip(color)
+                .map(|(&q, v)| (q as f32 - v).powi(2))
+                .sum::<f32>();
+            ((quantized, p), error)
+        })
+        .min_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map(|(endpoint, _)| endpoint)
+        .expect("there are two candidates")
+}
+
+fn unquantize_endpoint(color: [u8; 4], p_bit: u8) -> [u32; 4] {
+    color.map(|c| u32::from((c << 1) | p_bit))
+}
+
+/// Packs a mode 6 block.
+fn pack_block(endpoints: &Endpoints, indices: &[u8; 16]) -> [u8; 16] {
+    let mut bits = 0u128;
+    let mut offset = 0;
+    let mut put = |value: u8, count: u32| {
+        bits |= u128::from(value) << offset;
+        offset += count;
+    };
+
+    // Mode 6 is signalled by six zero bits followed by a one.
+    put(1 << 6, 7);
+    for c in 0..4 {
+        put(endpoints.colors[0][c], 7);
+        put(endpoints.colors[1][c], 7);
+    }
+    put(endpoints.p_bits[0], 1);
+    put(endpoints.p_bits[1], 1);
+    for (i, &index) in indices.iter().enumerate() {
+        put(index, if i == 0 { 3 } else { 4 });
+    }
+
+    bits.to_le_bytes()
+}
@@ -0,0 +1,28 @@
+//! Entry points for the benchmarks in `benches/`. These are not part of the public API.
+
+use bevy_image::{Image, TextureAccessError};
+
+use super::{
+    MipFilter, TextureKind, TilesetImportSettings,
+    linear_image::PixelCodec,
+    mips::{self, AlphaMix},
+};
+
+/// Fills each level after the first by downscaling the previous level, as during import.
+///
+/// If `generic` is set, pixels are always accessed through [`Image::get_color_at`] and
+/// [`Image::set_color_at`], rather than directly as bytes where the format allows it.
+pub fn generate_mips(
+    levels: &mut [Image],
+    mip_filter: MipFilter,
+    generic: bool,
+) -> Result<(), TextureAccessError> {
+    let codec = match levels.first() {
+        Some(_) if generic => PixelCodec::Color,
+        Some(base) => PixelCodec::for_format(base.texture_descriptor.format),
+        None => return Ok(()),
+    };
+    let alpha = AlphaMix::new(&TilesetImportSettings::default());
+
+    mips::generate_mips(levels, codec, mip_filter, alpha, TextureKind::Color)
+}
@@ -0,0 +1,72 @@
+use bevy_math::UVec2;
+use texpresso::{Format, Params};
+use wgpu_types::TextureFormat;
+
+use super::bc7;
+
+/// Encodes tiles into a GPU block-compressed format.
+///
+/// Tiles are built and mipped in an uncompressed [working format](Self::working_format), and each
+/// mip level is encoded once it is complete.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum BlockEncoder {
+    Bc1,
+    Bc3,
+    Bc7,
+}
+
+impl BlockEncoder {
+    /// Returns the encoder for `format`, or `None` if tiles in that format are not encoded.
+    pub fn for_format(format: TextureFormat) -> Option<Self> {
+        match format {
+            TextureFormat::Bc1RgbaUnorm | TextureFormat::Bc1RgbaUnormSrgb => Some(Self::Bc1),
+            TextureFormat::Bc3RgbaUnorm | TextureFormat::Bc3RgbaUnormSrgb => Some(Self::Bc3),
+            TextureFormat::Bc7RgbaUnorm | TextureFormat::Bc7RgbaUnormSrgb => Some(Self::Bc7),
+            _ => None,
+        }
+    }
+
+    /// Returns the format that tiles are built in before being encoded to `format`. This is
+    /// `format` itself if tiles in that format are not encoded.
+    pub fn working_format(format: TextureFormat) -> TextureFormat {
+        match Self::for_format(format) {
+            Some(_) if format.is_srgb() => TextureFormat::Rgba8UnormSrgb,
+            Some(_) => TextureFormat::Rgba8Unorm,
+            None => format,
+        }
+    }
+
+    /// Encodes an RGBA8 image of the given size, appending the blocks to `output`.
+    ///
+    /// Partial blocks at the right and bottom edges are padded by repeating the edge pixels.
+    pub fn encode(self, rgba: &[u8], size: UVec2, output: &mut Vec<u8>) {
+        let (width, height) = (size.x as usize, size.y as usize);
+        debug_assert_eq!(rgba.len(), 4 * width * height);
+
+        match self {
+            Self::Bc1 | Self::Bc3 => {
+                let format = match self {
+                    Self::Bc1 => Format::Bc1,
+                    _ => Format::Bc3,
+                };
+
+                let start = output.len();
+                output.resize(start + format.compressed_size(width, height), 0);
+                format.compress(rgba, width, height, Params::default(), &mut output[start..]);
+            }
+            Self::Bc7 => {
+                for by in (0..height).step_by(4) {
+                    for bx in (0..width).step_by(4) {
+                        let block = std::array::from_fn(|i| {
+                            let x = (bx + i % 4).min(width - 1);
+                            let y = (by + i / 4).min(height - 1);
+                            let j = 4 * (x + y * width);
+                            [rgba[j], rgba[j + 1], rgba[j + 2], rgba[j + 3]]
+                        });
+                        output.extend_from_slice(&bc7::encode_block(&block));
+                    }
+                }
+            }
+        }
+    }
+}
@@ -1,13 +1,35 @@
 use bevy_image::TextureAccessError;
+use bevy_math::UVec2;
 use thiserror::Error;
 use wgpu_types::TextureFormat;
 
+use super::TileTransform;
 use crate::{TileSourceIndex, layout::LayoutError};
 
 #[derive(Debug, Error)]
 pub enum ImportTilesetError {
     #[error("unsupported texture format: {0:?}")]
     UnsupportedFormat(TextureFormat),
+    #[error("two-channel normal maps require a two-channel texture format, but got {0:?}")]
+    TwoChannelFormat(TextureFormat),
+    #[error("tile size {tile_size} is not a multiple of the {texture_format:?} block size")]
+    BlockAlignment {
+        texture_format: TextureFormat,
+        tile_size: UVec2,
+    },
+    #[error("alpha test value {0} must be greater than 0 and less than 1")]
+    AlphaTest(f32),
+    #[error("atlas output requires at least one column")]
+    AtlasColumns,
+    #[error("texture format {0:?} is block-compressed, and cannot be packed into an atlas")]
+    AtlasFormat(TextureFormat),
+    #[error("array texture pages require at least one layer")]
+    ArrayLayers,
+    #[error("tile transform {transform:?} requires square tiles, but the tile size is {tile_size}")]
+    NonSquareTransform {
+        transform: TileTransform,
+        tile_size: UVec2,
+    },
     #[error("failed to generate mipmaps: {0}")]
     GenerateMips(TextureAccessError),
     #[error("error validating sources: {0}")]
@@ -25,6 +47,37 @@ pub enum ImportTilesetError {
         #[source]
         err: SourceError,
     },
+    #[error(
+        "in group {group:?}: tile {} from source {} is fully transparent, and was skipped by the tile filter",
+        tile_source.1,
+        tile_source.0
+    )]
+    TransparentGroupTile {
+        group: String,
+        tile_source: TileSourceIndex,
+    },
+    #[error(
+        "tile {} from source {} is missing, or has a different frame than in the main sources",
+        tile_source.1,
+        tile_source.0
+    )]
+    MissingLayerTile { tile_source: TileSourceIndex },
+    #[error(
+        "layer name {0:?} must not be empty or a number, so that it is not labeled like a page"
+    )]
+    LayerName(String),
+    #[error("layer {layer:?} has {found} textures, but the tileset has {expected} sources")]
+    LayerTextureCount {
+        layer: String,
+        expected: usize,
+        found: usize,
+    },
+    #[error("in layer {layer:?}: {err}")]
+    InLayer {
+        layer: String,
+        #[source]
+        err: Box<ImportTilesetError>,
+    },
 }
 
 impl ImportTilesetError {
@@ -39,6 +92,14 @@ impl ImportTilesetError {
             other => other,
         }
     }
+
+    /// Wraps an error that occurred while importing a companion layer.
+    pub(crate) fn in_layer(self, layer: &str) -> Self {
+        Self::InLayer {
+            layer: layer.into(),
+            err: Box::new(self),
+        }
+    }
 }
 
 #[derive(Debug, Error)]
@@ -53,6 +114,12 @@ pub enum SourceError {
         source_format: TextureFormat,
         expected: TextureFormat,
     },
+    #[error("source {source_id} pixels could not be read: {err}")]
+    PixelAccess {
+        source_id: usize,
+        #[source]
+        err: TextureAccessError,
+    },
     #[error("source {source_id} encountered a layout error: {err}")]
     SourceLayout {
         source_id: usize,
@@ -0,0 +1,380 @@
+use std::sync::LazyLock;
+
+use bevy_color::{Color, LinearRgba, Srgba, Xyza};
+use bevy_image::{Image, TextureAccessError, TextureFormatPixelInfo};
+use bevy_math::UVec2;
+use wgpu_types::{TextureDimension, TextureFormat};
+
+/// An image decoded to linear colors, which mipmaps are generated from.
+pub(crate) struct LinearImage {
+    size: UVec2,
+    pixels: Vec<LinearRgba>,
+}
+
+impl LinearImage {
+    /// Creates a transparent image.
+    pub fn new(size: UVec2) -> Self {
+        Self {
+            size,
+            pixels: vec![LinearRgba::NONE; size.element_product() as usize],
+        }
+    }
+
+    pub fn size(&self) -> UVec2 {
+        self.size
+    }
+
+    pub fn width(&self) -> u32 {
+        self.size.x
+    }
+
+    pub fn height(&self) -> u32 {
+        self.size.y
+    }
+
+    pub fn get(&self, x: u32, y: u32) -> LinearRgba {
+        self.pixels[self.index(x, y)]
+    }
+
+    pub fn set(&mut self, x: u32, y: u32, color: LinearRgba) {
+        let i = self.index(x, y);
+        self.pixels[i] = color;
+    }
+
+    pub fn pixels(&self) -> &[LinearRgba] {
+        &self.pixels
+    }
+
+    pub fn pixels_mut(&mut self) -> &mut [LinearRgba] {
+        &mut self.pixels
+    }
+
+    fn index(&self, x: u32, y: u32) -> usize {
+        debug_assert!(x < self.size.x && y < self.size.y);
+        (y * self.size.x + x) as usize
+    }
+}
+
+/// Converts between the pixels of an [`Image`] and a [`LinearImage`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum PixelCodec {
+    /// Reads and writes 8-bit unorm channels directly, using lookup tables for sRGB. This gives
+    /// the same results as [`PixelCodec::Color`], but is much faster.
+    Bytes { channels: usize, srgb: bool },
+    /// Uses [`Image::get_color_at`] and [`Image::set_color_at`], which support most uncompressed
+    /// formats.
+    Color,
+    /// Unpacks 32-bit packed and shared-exponent formats, which [`PixelCodec::Color`] does not
+    /// support.
+    Packed(PackedFormat),
+}
+
+/// A 32-bit packed pixel format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum PackedFormat {
+    /// [`TextureFormat::Rgb10a2Unorm`]
+    Rgb10a2Unorm,
+    /// [`TextureFormat::Rg11b10Ufloat`]
+    Rg11b10Ufloat,
+    /// [`TextureFormat::Rgb9e5Ufloat`]
+    Rgb9e5Ufloat,
+}
+
+impl PixelCodec {
+    /// Returns the fastest codec for images of `format`.
+    pub fn for_format(format: TextureFormat) -> Self {
+        match format {
+            TextureFormat::Rgba8Unorm => Self::Bytes {
+                channels: 4,
+                srgb: false,
+            },
+            TextureFormat::Rgba8UnormSrgb => Self::Bytes {
+                channels: 4,
+                srgb: true,
+            },
+            TextureFormat::Rg8Unorm => Self::Bytes {
+                channels: 2,
+                srgb: false,
+            },
+            TextureFormat::R8Unorm => Self::Bytes {
+                channels: 1,
+                srgb: false,
+            },
+            TextureFormat::Rgb10a2Unorm => Self::Packed(PackedFormat::Rgb10a2Unorm),
+            TextureFormat::Rg11b10Ufloat => Self::Packed(PackedFormat::Rg11b10Ufloat),
+            TextureFormat::Rgb9e5Ufloat => Self::Packed(PackedFormat::Rgb9e5Ufloat),
+            _ => Self::Color,
+        }
+    }
+
+    pub fn decode(self, image: &Image) -> Result<LinearImage, TextureAccessError> {
+        let mut linear = LinearImage::new(image.size());
+
+        match self {
+            Self::Bytes { channels, srgb } => {
+                let data = image.data.as_ref().expect("images are initialized");
+                for (pixel, bytes) in linear.pixels.iter_mut().zip(data.chunks_exact(channels)) {
+                    *pixel = decode_bytes(bytes, srgb);
+                }
+            }
+            Self::Color => {
+                for y in 0..image.height() {
+                    for x in 0..image.width() {
+                        linear.set(x, y, image.get_color_at(x, y)?.to_linear());
+                    }
+                }
+            }
+            Self::Packed(format) => {
+                let data = image.data.as_ref().expect("images are initialized");
+                for (pixel, bytes) in linear.pixels.iter_mut().zip(data.chunks_exact(4)) {
+                    let bits = u32::from_le_bytes(bytes.try_into().expect("pixels are 4 bytes"));
+                    *pixel = format.unpack(bits);
+                }
+            }
+        }
+
+        Ok(linear)
+    }
+
+    pub fn encode(self, linear: &LinearImage, image: &mut Image) -> Result<(), TextureAccessError> {
+        debug_assert_eq!(linear.size(), image.size());
+
+        match self {
+            Self::Bytes { channels, srgb } => {
+                let data = image.data.as_mut().expect("images are initialized");
+                for (pixel, bytes) in linear.pixels.iter().zip(data.chunks_exact_mut(channels)) {
+                    encode_bytes(*pixel, srgb, bytes);
+                }
+            }
+            Self::Color => {
+                for y in 0..image.height() {
+                    for x in 0..image.width() {
+                        image.set_color_at(x, y, Color::from(linear.get(x, y)))?;
+                    }
+                }
+            }
+            Self::Packed(format) => {
+                let data = image.data.as_mut().expect("images are initialized");
+                for (pixel, bytes) in linear.pixels.iter().zip(data.chunks_exact_mut(4)) {
+                    bytes.copy_from_slice(&format.pack(*pixel).to_le_bytes());
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Converts `image` to `format` pixel by pixel, keeping each channel in place.
+///
+/// Returns `None` if either format is unsupported.
+pub(crate) fn convert_channels(image: &Image, format: TextureFormat) -> Option<Image> {
+    let linear = PixelCodec::for_format(image.texture_descriptor.format)
+        .decode(image)
+        .ok()?;
+
+    let mut converted = Image::new_fill(
+        image.texture_descriptor.size,
+        TextureDimension::D2,
+        &vec![0; format.pixel_size().ok()?],
+        format,
+        image.asset_usage,
+    );
+    PixelCodec::for_format(format)
+        .encode(&linear, &mut converted)
+        .ok()?;
+    Some(converted)
+}
+
+/// Decodes a single pixel, expanding it to RGBA in the same way as [`Image::get_color_at`].
+fn decode_bytes(bytes: &[u8], srgb: bool) -> LinearRgba {
+    let unorm = |byte: u8| f32::from(byte) / 255.0;
+    let color = |byte: u8| {
+        if srgb {
+            SRGB_TO_LINEAR[usize::from(byte)]
+        } else {
+            unorm(byte)
+        }
+    };
+
+    match *bytes {
+        [r, g, b, a] => LinearRgba::new(color(r), color(g), color(b), unorm(a)),
+        [r, g] => LinearRgba::rgb(unorm(r), unorm(g), 0.0),
+        [r] => LinearRgba::rgb(unorm(r), unorm(r), unorm(r)),
+        _ => unreachable!("unsupported channel count"),
+    }
+}
+
+/// Encodes a single pixel, truncating each channel in the same way as [`Image::set_color_at`].
+fn encode_bytes(pixel: LinearRgba, srgb: bool, bytes: &mut [u8]) {
+    let unorm = |value: f32| (value * 255.0) as u8;
+    let color = |value: f32| {
+        if srgb {
+            LINEAR_TO_SRGB.partition_point(|&threshold| threshold <= value) as u8
+        } else {
+            unorm(value)
+        }
+    };
+
+    match bytes {
+        [r, g, b, a] => {
+            *r = color(pixel.red);
+            *g = color(pixel.green);
+            *b = color(pixel.blue);
+            *a = unorm(pixel.alpha);
+        }
+        [r, g] => {
+            *r = unorm(pixel.red);
+            *g = unorm(pixel.green);
+        }
+        [r] => *r = unorm(Xyza::from(pixel).y),
+        _ => unreachable!("unsupported channel count"),
+    }
+}
+
+impl PackedFormat {
+    fn unpack(self, bits: u32) -> LinearRgba {
+        match self {
+            Self::Rgb10a2Unorm => {
+                let unorm = |shift: u32, max: u32| ((bits >> shift) & max) as f32 / max as f32;
+                LinearRgba::new(
+                    unorm(0, 0x3ff),
+                    unorm(10, 0x3ff),
+                    unorm(20, 0x3ff),
+                    unorm(30, 0x3),
+                )
+            }
+            Self::Rg11b10Ufloat => LinearRgba::rgb(
+                unpack_ufloat(bits & 0x7ff, 6),
+                unpack_ufloat((bits >> 11) & 0x7ff, 6),
+                unpack_ufloat(bits >> 22, 5),
+            ),
+            Self::Rgb9e5Ufloat => {
+                let exponent = (bits >> 27) as i32;
+                let scale = 2f32.powi(exponent - UFLOAT_BIAS - RGB9E5_MANTISSA_BITS);
+                let mantissa = |shift: u32| ((bits >> shift) & 0x1ff) as f32 * scale;
+                LinearRgba::rgb(mantissa(0), mantissa(9), mantissa(18))
+            }
+        }
+    }
+
+    /// Packs a pixel, rounding each channel to the nearest representable value. Formats without
+    /// an alpha channel ignore it.
+    fn pack(self, pixel: LinearRgba) -> u32 {
+        match self {
+            Self::Rgb10a2Unorm => {
+                let unorm =
+                    |value: f32, max: u32| (value.clamp(0.0, 1.0) * max as f32).round() as u32;
+                unorm(pixel.red, 0x3ff)
+                    | unorm(pixel.green, 0x3ff) << 10
+                    | unorm(pixel.blue, 0x3ff) << 20
+                    | unorm(pixel.alpha, 0x3) << 30
+            }
+            Self::Rg11b10Ufloat => {
+                pack_ufloat(pixel.red, 6)
+                    | pack_ufloat(pixel.green, 6) << 11
+                    | pack_ufloat(pixel.blue, 5) << 22
+            }
+            Self::Rgb9e5Ufloat => pack_rgb9e5(pixel),
+        }
+    }
+}
+
+/// The exponent bias of the 5-bit exponents in the packed float formats, which is the same as
+/// `f16`.
+const UFLOAT_BIAS: i32 = 15;
+const RGB9E5_MANTISSA_BITS: i32 = 9;
+
+/// Unpacks an unsigned float with a 5-bit exponent and `mantissa_bits` of mantissa.
+fn unpack_ufloat(bits: u32, mantissa_bits: u32) -> f32 {
+    let exponent = (bits >> mantissa_bits) as i32;
+    let mantissa = (bits & ((1 << mantissa_bits) - 1)) as f32 / (1 << mantissa_bits) as f32;
+    match exponent {
+        0 => mantissa * 2f32.powi(1 - UFLOAT_BIAS),
+        31 if mantissa == 0.0 => f32::INFINITY,
+        31 => f32::NAN,
+        _ => (1.0 + mantissa) * 2f32.powi(exponent - UFLOAT_BIAS),
+    }
+}
+
+/// Packs a value into an unsigned float with a 5-bit exponent and `mantissa_bits` of mantissa.
+/// Negative values and NaN become zero, and values that are too large saturate to the largest
+/// finite value.
+fn pack_ufloat(value: f32, mantissa_bits: u32) -> u32 {
+    let max_finite = (30 << mantissa_bits) | ((1 << mantissa_bits) - 1);
+    if value.is_nan() || value <= 0.0 {
+        return 0;
+    }
+
+    let exponent = value.to_bits() as i32 >> 23;
+    let packed_exponent = exponent - 127 + UFLOAT_BIAS;
+    let packed = if packed_exponent <= 0 {
+        // Denormal, where the mantissa counts multiples of the smallest step
+        (value * 2f32.powi(UFLOAT_BIAS - 1) * (1 << mantissa_bits) as f32).round() as u32
+    } else {
+        let shift = 23 - mantissa_bits;
+        let mantissa = value.to_bits() & 0x7f_ffff;
+        let rounding = (mantissa >> (shift - 1)) & 1;
+        // A carry out of the mantissa correctly increments the exponent
+        ((packed_exponent as u32) << mantissa_bits) + (mantissa >> shift) + rounding
+    };
+    packed.min(max_finite)
+}
+
+/// Packs a pixel with a shared exponent, as described by the `EXT_texture_shared_exponent`
+/// extension.
+fn pack_rgb9e5(pixel: LinearRgba) -> u32 {
+    let max_mantissa = (1 << RGB9E5_MANTISSA_BITS) - 1;
+    let max_value =
+        max_mantissa as f32 / (1 << RGB9E5_MANTISSA_BITS) as f32 * 2f32.powi(31 - UFLOAT_BIAS);
+    let clamp = |value: f32| {
+        if value > 0.0 {
+            value.min(max_value)
+        } else {
+            0.0
+        }
+    };
+    let (r, g, b) = (clamp(pixel.red), clamp(pixel.green), clamp(pixel.blue));
+
+    let max_channel = r.max(g).max(b);
+    let floor_log2 = if max_channel > 0.0 {
+        (max_channel.to_bits() >> 23) as i32 - 127
+    } else {
+        i32::MIN
+    };
+    let mut exponent = floor_log2.max(-UFLOAT_BIAS - 1) + 1 + UFLOAT_BIAS;
+    let mut scale = 2f32.powi(exponent - UFLOAT_BIAS - RGB9E5_MANTISSA_BITS);
+    if (max_channel / scale).round() as u32 > max_mantissa {
+        exponent += 1;
+        scale *= 2.0;
+    }
+
+    let mantissa = |value: f32| ((value / scale).round() as u32).min(max_mantissa);
+    mantissa(r) | mantissa(g) << 9 | mantissa(b) << 18 | (exponent as u32) << 27
+}
+
+/// The linear value of each 8-bit sRGB value.
+static SRGB_TO_LINEAR: LazyLock<[f32; 256]> =
+    LazyLock::new(|| std::array::from_fn(|i| Srgba::gamma_function(i as f32 / 255.0)));
+
+/// The smallest linear value that encodes to each 8-bit sRGB value from 1 to 255, so that the
+/// encoded value of `x` is the number of thresholds at or below `x`.
+static LINEAR_TO_SRGB: LazyLock<[f32; 255]> = LazyLock::new(|| {
+    let encode = |value: f32| (Srgba::gamma_function_inverse(value) * 255.0) as u8;
+
+    std::array::from_fn(|i| {
+        let target = i as u8 + 1;
+
+        // Non-negative floats are ordered the same as their bits
+        let (mut lo, mut hi) = (0u32, 2.0f32.to_bits());
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            if encode(f32::from_bits(mid)) >= target {
+                hi = mid;
+            } else {
+                lo = mid + 1;
+            }
+        }
+        f32::from_bits(lo)
+    })
+});
@@ -0,0 +1,452 @@
+use std::f32::consts::PI;
+
+use bevy_color::{Alpha, LinearRgba};
+use bevy_image::{Image, TextureAccessError};
+use bevy_math::{UVec2, Vec3};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    TextureKind, TilesetImportSettings,
+    linear_image::{LinearImage, PixelCodec},
+};
+
+/// The filter used to downscale each mip level from the previous one.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MipFilter {
+    /// Averages 2x2 blocks of pixels, falling back to bilinear interpolation for levels that are
+    /// not exactly half the size of the previous level.
+    #[default]
+    Box,
+    /// A triangle (tent) filter scaled to the footprint of each target pixel. This is slightly
+    /// softer than [`MipFilter::Box`].
+    Bilinear,
+    /// A three-lobed Lanczos filter. This keeps more detail than [`MipFilter::Box`], but may
+    /// produce ringing around sharp edges.
+    Lanczos3,
+    /// A Kaiser-windowed sinc filter. This is nearly as sharp as [`MipFilter::Lanczos3`], with
+    /// less ringing.
+    Kaiser,
+    /// Keeps the top-left pixel of each block. This preserves hard edges and the color palette,
+    /// which suits pixel art.
+    Nearest,
+}
+
+impl MipFilter {
+    /// Downscales `src` into `tgt`.
+    pub(crate) fn downscale(self, src: &LinearImage, tgt: &mut LinearImage, alpha: AlphaMix) {
+        match self {
+            Self::Box if src.size() == 2 * tgt.size() => downscale_image_half(src, tgt, alpha),
+            Self::Box => downscale_image_bilinear(src, tgt, alpha),
+            Self::Bilinear => downscale_image_kernel(src, tgt, 1.0, triangle, alpha),
+            Self::Lanczos3 => downscale_image_kernel(src, tgt, 3.0, lanczos3, alpha),
+            Self::Kaiser => downscale_image_kernel(src, tgt, 3.0, kaiser, alpha),
+            Self::Nearest => downscale_image_nearest(src, tgt),
+        }
+    }
+}
+
+/// How transparent pixels are handled when blending them into lower mip levels.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum AlphaMipMode {
+    /// Any blended pixel that includes a transparent pixel becomes transparent. This avoids
+    /// bleeding the color of transparent pixels into their neighbors, but erodes sprites at lower
+    /// mip levels.
+    #[default]
+    DiscardAll,
+    /// Transparent pixels are left out of the blend, and the remaining pixels are averaged. The
+    /// edges of sprites keep their color and opacity, so sprites grow slightly at lower mip
+    /// levels.
+    IgnoreTransparent,
+    /// Colors are weighted by their alpha before blending, so transparent pixels contribute to
+    /// the opacity of the result but not its color.
+    Premultiplied,
+    /// Blends like [`AlphaMipMode::Premultiplied`], then scales the alpha of each mip level so
+    /// that the fraction of pixels above `alpha_test` matches the base level. This keeps
+    /// alpha-tested sprites, such as foliage, from thinning out or vanishing at a distance.
+    ///
+    /// `alpha_test` must be greater than 0 and less than 1.
+    PreserveCoverage { alpha_test: f32 },
+}
+
+/// The alpha handling used when blending pixels into a mip level.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct AlphaMix {
+    pub mode: AlphaMipMode,
+    /// Pixels with an alpha at or below this value are considered transparent.
+    pub cutoff: f32,
+    /// Whether the color channels have already been premultiplied by alpha.
+    pub premultiplied: bool,
+}
+
+impl AlphaMix {
+    pub fn new(settings: &TilesetImportSettings) -> Self {
+        Self {
+            mode: settings.alpha_mip_mode,
+            cutoff: settings.alpha_cutoff,
+            premultiplied: settings.premultiply_alpha,
+        }
+    }
+
+    /// Blends colors with weights that sum to one.
+    fn mix(self, samples: &[(LinearRgba, f32)]) -> LinearRgba {
+        let mut linear_sum = LinearRgba::NONE;
+        match self.mode {
+            AlphaMipMode::DiscardAll => {
+                for &(linear, weight) in samples {
+                    if linear.alpha <= self.cutoff {
+                        return LinearRgba::NONE;
+                    }
+                    linear_sum += linear * weight;
+                }
+            }
+            AlphaMipMode::IgnoreTransparent => {
+                let mut total_weight = 0.0;
+                for &(linear, weight) in samples {
+                    if linear.alpha > self.cutoff {
+                        linear_sum += linear * weight;
+                        total_weight += weight;
+                    }
+                }
+
+                if total_weight <= 0.0 {
+                    return LinearRgba::NONE;
+                }
+                linear_sum /= total_weight;
+            }
+            AlphaMipMode::Premultiplied | AlphaMipMode::PreserveCoverage { .. }
+                if self.premultiplied =>
+            {
+                for &(linear, weight) in samples {
+                    linear_sum += linear * weight;
+                }
+            }
+            AlphaMipMode::Premultiplied | AlphaMipMode::PreserveCoverage { .. } => {
+                for &(linear, weight) in samples {
+                    let alpha_weight = linear.alpha * weight;
+                    linear_sum.red += linear.red * alpha_weight;
+                    linear_sum.green += linear.green * alpha_weight;
+                    linear_sum.blue += linear.blue * alpha_weight;
+                    linear_sum.alpha += alpha_weight;
+                }
+
+                if linear_sum.alpha <= self.cutoff {
+                    return LinearRgba::NONE;
+                }
+                linear_sum = LinearRgba {
+                    alpha: linear_sum.alpha,
+                    ..linear_sum / linear_sum.alpha
+                };
+            }
+        }
+
+        // Negative lobes can push channels out of range
+        let linear_sum = LinearRgba::new(
+            linear_sum.red.max(0.0),
+            linear_sum.green.max(0.0),
+            linear_sum.blue.max(0.0),
+            linear_sum.alpha.clamp(0.0, 1.0),
+        );
+
+        if linear_sum.alpha <= self.cutoff {
+            LinearRgba::NONE
+        } else {
+            linear_sum
+        }
+    }
+}
+
+/// Fills each level after the first by downscaling the previous level.
+pub(crate) fn generate_mips(
+    levels: &mut [Image],
+    codec: PixelCodec,
+    mip_filter: MipFilter,
+    alpha: AlphaMix,
+    kind: TextureKind,
+) -> Result<(), TextureAccessError> {
+    let decode = |image: &Image| {
+        let mut linear = codec.decode(image)?;
+        if kind.is_two_channel() {
+            reconstruct_normal_z(&mut linear);
+        }
+        Ok(linear)
+    };
+
+    let Some(base) = levels.first() else {
+        return Ok(());
+    };
+    let mut src = decode(base)?;
+
+    let base_coverage = match alpha.mode {
+        AlphaMipMode::PreserveCoverage { alpha_test } => {
+            Some((alpha_coverage(&src, alpha_test), alpha_test))
+        }
+        _ => None,
+    };
+
+    for m in 1..levels.len() {
+        let mut tgt = LinearImage::new(levels[m].size());
+        mip_filter.downscale(&src, &mut tgt, alpha);
+        if let Some((coverage, alpha_test)) = base_coverage {
+            scale_to_coverage(&mut tgt, coverage, alpha_test, alpha.premultiplied);
+        }
+        if let TextureKind::Normal { .. } = kind {
+            renormalize(&mut tgt);
+        }
+        codec.encode(&tgt, &mut levels[m])?;
+
+        // Downscale from the level as it is stored, rather than at full precision
+        if m + 1 < levels.len() {
+            src = decode(&levels[m])?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Fills in the blue channel of a two-channel normal map from its red and green channels.
+fn reconstruct_normal_z(image: &mut LinearImage) {
+    for pixel in image.pixels_mut() {
+        let (x, y) = (pixel.red * 2.0 - 1.0, pixel.green * 2.0 - 1.0);
+        let z = (1.0 - x * x - y * y).max(0.0).sqrt();
+        pixel.blue = z * 0.5 + 0.5;
+    }
+}
+
+/// Scales each encoded normal of a normal map back to unit length. Zero-length normals, such as
+/// from opposing normals cancelling out, are replaced by one facing straight out.
+fn renormalize(image: &mut LinearImage) {
+    for pixel in image.pixels_mut() {
+        let n = Vec3::new(pixel.red, pixel.green, pixel.blue) * 2.0 - 1.0;
+        let n = n.try_normalize().unwrap_or(Vec3::Z) * 0.5 + 0.5;
+        (pixel.red, pixel.green, pixel.blue) = (n.x, n.y, n.z);
+    }
+}
+
+/// Returns the fraction of pixels in `image` with an alpha above `alpha_test`.
+fn alpha_coverage(image: &LinearImage, alpha_test: f32) -> f32 {
+    let covered = image
+        .pixels()
+        .iter()
+        .filter(|pixel| pixel.alpha > alpha_test)
+        .count();
+    covered as f32 / image.pixels().len() as f32
+}
+
+/// Scales the alpha of every pixel in `image` so that the fraction of pixels with an alpha above
+/// `alpha_test` is as close as possible to `target_coverage`. If the image is `premultiplied`,
+/// the color channels are scaled along with alpha.
+fn scale_to_coverage(
+    image: &mut LinearImage,
+    target_coverage: f32,
+    alpha_test: f32,
+    premultiplied: bool,
+) {
+    if target_coverage <= 0.0 {
+        return;
+    }
+
+    // Coverage only decreases as the threshold increases, so search for the threshold which
+    // would give the target coverage, then scale alpha so that threshold maps to `alpha_test`.
+    let (mut lo, mut hi) = (0.0f32, 1.0f32);
+    for _ in 0..16 {
+        let mid = 0.5 * (lo + hi);
+        if alpha_coverage(image, mid) > target_coverage {
+            lo = mid;
+        } else {
+            hi = mid;
+        }
+    }
+
+    let error = |threshold| (alpha_coverage(image, threshold) - target_coverage).abs();
+    let threshold = if error(lo) <= error(hi) { lo } else { hi };
+    if threshold == 0.0 {
+        // No scale can bring the coverage any closer
+        return;
+    }
+    let scale = alpha_test / threshold;
+    if (scale - 1.0).abs() < 1e-3 {
+        return;
+    }
+
+    for pixel in image.pixels_mut() {
+        let alpha = (pixel.alpha * scale).min(1.0);
+        *pixel = if premultiplied && pixel.alpha > 0.0 {
+            *pixel * (alpha / pixel.alpha)
+        } else {
+            pixel.with_alpha(alpha)
+        };
+    }
+}
+
+/// Downscales `src` into `tgt` by blending 2x2 blocks of pixels.
+fn downscale_image_half(src: &LinearImage, tgt: &mut LinearImage, alpha: AlphaMix) {
+    debug_assert_eq!(src.size(), tgt.size() * 2);
+
+    for ty in 0..tgt.height() {
+        for tx in 0..tgt.width() {
+            let sx = 2 * tx;
+            let sy = 2 * ty;
+
+            let mix_color = alpha.mix(&[
+                (src.get(sx, sy), 0.25),
+                (src.get(sx + 1, sy), 0.25),
+                (src.get(sx, sy + 1), 0.25),
+                (src.get(sx + 1, sy + 1), 0.25),
+            ]);
+
+            tgt.set(tx, ty, mix_color);
+        }
+    }
+}
+
+/// Downscales `src` into `tgt` using bilinear interpolation.
+fn downscale_image_bilinear(src: &LinearImage, tgt: &mut LinearImage, alpha: AlphaMix) {
+    let scale = src.size().as_vec2() / tgt.size().as_vec2();
+
+    for ty in 0..tgt.height() {
+        for tx in 0..tgt.width() {
+            let sxy_f = scale * UVec2::new(tx, ty).as_vec2();
+            let sxy_i = sxy_f.floor();
+
+            let t = sxy_f - sxy_i;
+            let sx = sxy_i.x as u32;
+            let sy = sxy_i.y as u32;
+            // Neighbours past the last row or column are clamped to the edge
+            let nx = (sx + 1).min(src.width() - 1);
+            let ny = (sy + 1).min(src.height() - 1);
+
+            let mix_color = alpha.mix(&[
+                (src.get(sx, sy), (1.0 - t.x) * (1.0 - t.y)),
+                (src.get(nx, sy), t.x * (1.0 - t.y)),
+                (src.get(sx, ny), (1.0 - t.x) * t.y),
+                (src.get(nx, ny), t.x * t.y),
+            ]);
+
+            tgt.set(tx, ty, mix_color);
+        }
+    }
+}
+
+/// Downscales `src` into `tgt` by keeping the top-left source pixel of each target pixel.
+fn downscale_image_nearest(src: &LinearImage, tgt: &mut LinearImage) {
+    let scale = src.size().as_vec2() / tgt.size().as_vec2();
+
+    for ty in 0..tgt.height() {
+        for tx in 0..tgt.width() {
+            let sxy = (scale * UVec2::new(tx, ty).as_vec2()).as_uvec2();
+            tgt.set(tx, ty, src.get(sxy.x, sxy.y));
+        }
+    }
+}
+
+/// Downscales `src` into `tgt` using a separable filter `kernel` which is zero outside of
+/// `-radius..radius`. The kernel is stretched to cover the footprint of each target pixel.
+fn downscale_image_kernel(
+    src: &LinearImage,
+    tgt: &mut LinearImage,
+    radius: f32,
+    kernel: fn(f32) -> f32,
+    alpha: AlphaMix,
+) {
+    let x_weights = kernel_weights(src.width(), tgt.width(), radius, kernel);
+    let y_weights = kernel_weights(src.height(), tgt.height(), radius, kernel);
+
+    let mut samples = Vec::new();
+    for (ty, y_weights) in y_weights.iter().enumerate() {
+        for (tx, x_weights) in x_weights.iter().enumerate() {
+            samples.clear();
+            for &(sy, wy) in y_weights {
+                for &(sx, wx) in x_weights {
+                    samples.push((src.get(sx, sy), wx * wy));
+                }
+            }
+
+            tgt.set(tx as u32, ty as u32, alpha.mix(&samples));
+        }
+    }
+}
+
+/// Returns the normalized `(source_index, weight)` pairs contributing to each target pixel along
+/// one axis. Source pixels beyond the edges are clamped to the nearest edge pixel.
+fn kernel_weights(
+    src_len: u32,
+    tgt_len: u32,
+    radius: f32,
+    kernel: fn(f32) -> f32,
+) -> Vec<Vec<(u32, f32)>> {
+    let scale = (src_len as f32 / tgt_len as f32).max(1.0);
+    let support = radius * scale;
+
+    (0..tgt_len)
+        .map(|t| {
+            let center = (t as f32 + 0.5) * src_len as f32 / tgt_len as f32;
+            let start = (center - support).floor() as i64;
+            let end = (center + support).ceil() as i64;
+
+            let mut weights: Vec<(u32, f32)> = Vec::new();
+            for s in start..end {
+                let weight = kernel((s as f32 + 0.5 - center) / scale);
+                if weight == 0.0 {
+                    continue;
+                }
+
+                // Clamped indices are adjacent, so they can be merged as they are found
+                let s = s.clamp(0, i64::from(src_len) - 1) as u32;
+                match weights.last_mut() {
+                    Some((last, last_weight)) if *last == s => *last_weight += weight,
+                    _ => weights.push((s, weight)),
+                }
+            }
+
+            let total = weights.iter().map(|(_, weight)| weight).sum::<f32>();
+            for (_, weight) in &mut weights {
+                *weight /= total;
+            }
+            weights
+        })
+        .collect()
+}
+
+fn triangle(x: f32) -> f32 {
+    (1.0 - x.abs()).max(0.0)
+}
+
+fn sinc(x: f32) -> f32 {
+    if x == 0.0 {
+        1.0
+    } else {
+        (PI * x).sin() / (PI * x)
+    }
+}
+
+fn lanczos3(x: f32) -> f32 {
+    if x.abs() < 3.0 {
+        sinc(x) * sinc(x / 3.0)
+    } else {
+        0.0
+    }
+}
+
+fn kaiser(x: f32) -> f32 {
+    const RADIUS: f32 = 3.0;
+    const BETA: f32 = 4.0;
+
+    let t = x / RADIUS;
+    if t.abs() < 1.0 {
+        sinc(x) * bessel_i0(BETA * (1.0 - t * t).sqrt()) / bessel_i0(BETA)
+    } else {
+        0.0
+    }
+}
+
+/// The zeroth-order modified Bessel function of the first kind.
+fn bessel_i0(x: f32) -> f32 {
+    let mut sum = 1.0;
+    let mut term = 1.0;
+    let half_x_sq = x * x / 4.0;
+    for k in 1..20 {
+        term *= half_x_sq / (k * k) as f32;
+        sum += term;
+    }
+    sum
+}
@@ -6,50 +6,195 @@ use bevy_asset::{
     processor::{Process, ProcessContext, ProcessError},
 };
 use bevy_image::Image;
-use bevy_math::UVec2;
-use bevy_platform::collections::{HashMap, hash_map::Entry};
+use bevy_log::info;
+use bevy_math::{URect, UVec2};
+use bevy_platform::collections::{HashMap, HashSet, hash_map::Entry};
 use bevy_reflect::TypePath;
 use serde::{Deserialize, Serialize};
 use wgpu_types::TextureFormat;
 
 use crate::{
     TileSourceIndex,
-    format::TilesetFile,
-    layout::{TilesetLayout, TilesetSourceFrames},
+    format::{Compression, OutputLayout, Storage, TileTrim, TilesetFile, TilesetFileLayer},
+    layout::{FrameAlign, FramePadding, TileFrame, TilesetLayout, TilesetSourceFrames},
     loader::{TilesetLoader, TilesetLoaderSettings},
+    parallel::par_map_init,
 };
 
+mod bc7;
+#[cfg(feature = "bench")]
+#[doc(hidden)]
+pub mod bench;
+mod block_encoder;
 mod error;
+mod linear_image;
+mod mips;
 mod texture_builder;
+mod transform;
 
+use block_encoder::BlockEncoder;
 pub use error::*;
-use texture_builder::TextureBuilder;
+use linear_image::convert_channels;
+pub use mips::{AlphaMipMode, MipFilter};
+use texture_builder::{
+    TextureAssembler, TextureBuilder, is_transparent, source_tile, visible_bounds,
+};
+pub use transform::{TileTransform, TileVariant};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct TilesetImportSettings {
     /// Sets a desired texture format for all imported tilesets.
     ///
     /// If a source image cannot be converted to this format, the import will fail with an error.
+    ///
+    /// The BC1, BC3, and BC7 block-compressed formats are also supported. In this case sources
+    /// are converted to [`TextureFormat::Rgba8Unorm`] (or its sRGB equivalent), and each tile is
+    /// encoded after its mipmaps are generated. The tile size must be a multiple of 4.
+    ///
+    /// BC7 blocks are only encoded with mode 6, which fits a single pair of endpoints to each
+    /// block. This is fast and handles alpha well, but blocks with several distinct colors lose
+    /// more detail than a full BC7 encoder would give.
     pub texture_format: Option<TextureFormat>,
+    /// What the pixels of the tileset represent. Defaults to [`TextureKind::Color`].
+    pub texture_kind: TextureKind,
     /// If set to `true`, mipmaps will be generated for each tile.
     ///
-    /// Mipmap generation is limited to texture formats supported by [`Image::get_color_at`].
+    /// Mipmap generation is limited to texture formats supported by [`Image::get_color_at`], along
+    /// with the packed [`TextureFormat::Rgb10a2Unorm`], [`TextureFormat::Rg11b10Ufloat`], and
+    /// [`TextureFormat::Rgb9e5Ufloat`] formats. Mipmaps of HDR formats keep values above `1.0`.
     pub generate_mips: bool,
-    /// A deflate [compression level][flate2::Compression] to use for the texture, from 0-9.
-    /// 0 leaves the data uncompressed, and 9 means "take as long as you want".
-    pub compression: u32,
+    /// The filter used to generate each mip level from the previous one. Defaults to
+    /// [`MipFilter::Box`].
+    pub mip_filter: MipFilter,
+    /// How transparent pixels are blended into lower mip levels. Defaults to
+    /// [`AlphaMipMode::DiscardAll`].
+    ///
+    /// Sprites with soft or thin edges, such as foliage, tend to vanish at a distance with the
+    /// default mode. [`AlphaMipMode::PreserveCoverage`] is usually the best choice for
+    /// alpha-tested tiles.
+    pub alpha_mip_mode: AlphaMipMode,
+    /// Pixels with an alpha at or below this value are treated as fully transparent when
+    /// generating mipmaps. Defaults to `1e-4`.
+    pub alpha_cutoff: f32,
+    /// If set to `true`, the color channels of each tile are multiplied by its alpha before
+    /// mipmaps are generated, and the tileset is marked as premultiplied so that it can be
+    /// rendered with premultiplied alpha blending. Defaults to `false`.
+    ///
+    /// Mipmaps are then blended in premultiplied space, so [`AlphaMipMode::Premultiplied`]
+    /// averages the stored colors as-is.
+    pub premultiply_alpha: bool,
+    /// If set to `true`, tiles with identical base-level pixels are stored only once, even if
+    /// they come from different sources or tile indices. Defaults to `false`.
+    ///
+    /// Group references to a duplicate tile resolve to the tile index of its first occurrence.
+    /// The merged tiles are logged at the info level.
+    pub dedup_pixels: bool,
+    /// If set, each frame is trimmed to the bounding box of its visible pixels before it is
+    /// placed in its tile. Defaults to `None`.
+    ///
+    /// The [`TileTrim`] of each tile is recorded in the tileset, so that its visual bounds are
+    /// known at runtime. Tiles merged by [`TilesetImportSettings::dedup_pixels`] keep the trim
+    /// of their first occurrence.
+    pub trim: Option<FrameTrim>,
+    /// The compression to use for the tileset file. Defaults to [`Compression::Deflate`] with
+    /// level 1.
+    ///
+    /// Stronger compression produces smaller files at the expense of import time, while the
+    /// choice of backend mostly affects load time. [`Compression::Lz4`] is the fastest to
+    /// decode.
+    pub compression: Compression,
+    /// How the texture data is laid out in the tileset file. Defaults to
+    /// [`Storage::Contiguous`].
+    ///
+    /// [`Storage::Chunked`] compresses each tile and mip level independently, which allows them
+    /// to be read individually and decompressed in parallel, at some cost in file size.
+    pub storage: Storage,
+    /// How the tiles are arranged in the loaded texture. Defaults to [`OutputLayout::Array`].
+    ///
+    /// With [`OutputLayout::Atlas`], the number of mip levels is limited so that the tile size
+    /// and padding can be halved evenly at every level, and block-compressed formats are
+    /// rejected.
+    pub output_layout: OutputLayout,
+    /// If set, the loaded tileset textures are split into pages of at most this many array
+    /// layers, for targets with a low `max_texture_array_layers` limit such as WebGL2. Defaults
+    /// to `None`.
+    ///
+    /// Tiles keep their [`TileIndex`](crate::TileIndex), and
+    /// [`Tileset::tile_page`](crate::Tileset::tile_page) gives the page and array layer of each.
+    /// This has no effect with [`OutputLayout::Atlas`].
+    pub max_array_layers: Option<u32>,
+    /// If set to `true`, tiles are copied and mipped in parallel. Defaults to `true`.
+    ///
+    /// The output is identical either way, so this is mostly useful for profiling, or for
+    /// limiting the importer to a single thread.
+    pub parallel: bool,
 }
 
 impl Default for TilesetImportSettings {
     fn default() -> Self {
         Self {
             texture_format: None,
+            texture_kind: TextureKind::Color,
             generate_mips: false,
-            compression: 1,
+            mip_filter: MipFilter::Box,
+            alpha_mip_mode: AlphaMipMode::DiscardAll,
+            alpha_cutoff: 1e-4,
+            premultiply_alpha: false,
+            dedup_pixels: false,
+            trim: None,
+            compression: Compression::Deflate(1),
+            storage: Storage::Contiguous,
+            output_layout: OutputLayout::Array,
+            max_array_layers: None,
+            parallel: true,
         }
     }
 }
 
+/// What the pixels of a tileset represent, which determines its texture format and how mipmaps
+/// are generated.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TextureKind {
+    /// Colors, which may be stored in an sRGB format.
+    #[default]
+    Color,
+    /// Tangent-space normals, with each unit vector `n` stored as `0.5 * n + 0.5` in the RGB
+    /// channels. Mipmaps are renormalized after filtering.
+    ///
+    /// If `two_channel` is `true`, only the X and Y components are stored, and Z is reconstructed
+    /// as `sqrt(1 - x² - y²)` when generating mipmaps. This requires a two-channel texture format,
+    /// which defaults to [`TextureFormat::Rg8Unorm`].
+    Normal { two_channel: bool },
+    /// Other non-color data, such as roughness or height.
+    Linear,
+}
+
+impl TextureKind {
+    /// Returns `true` if the pixels are stored as-is, rather than gamma-encoded.
+    ///
+    /// Sources and texture formats tagged as sRGB are reinterpreted as their linear equivalents.
+    fn is_linear(self) -> bool {
+        self != Self::Color
+    }
+
+    pub(crate) fn is_two_channel(self) -> bool {
+        self == Self::Normal { two_channel: true }
+    }
+}
+
+/// How frames are trimmed. See [`TilesetImportSettings::trim`].
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct FrameTrim {
+    /// Pixels with an alpha at or below this value are trimmed. Frames with no pixels above it
+    /// are left untrimmed.
+    pub alpha_threshold: f32,
+    /// Where the trimmed frame is placed within its tile. If `None`, the trimmed pixels stay
+    /// where they were in the untrimmed frame.
+    #[serde(default)]
+    pub align: Option<FrameAlign>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TilesetImporterSettings<L: AssetLoader<Asset = TilesetImportData>> {
     pub source_settings: L::Settings,
@@ -96,19 +241,19 @@ impl<L: AssetLoader<Asset = TilesetImportData>> Process for TilesetImporter<L> {
             .take::<L::Asset>()
             .expect("loader type is known");
 
-        let TilesetImportSettings {
-            texture_format,
-            generate_mips,
-            compression,
-        } = settings.import_settings;
+        let import_settings = &settings.import_settings;
 
         let tileset_file = tileset_data
-            .import(texture_format, generate_mips)
+            .import(import_settings)
             .map_err(|err| ProcessError::AssetTransformError(err.into()))?;
 
         async move {
             let mut bytes = Vec::new();
-            tileset_file.write(compression, &mut bytes)?;
+            tileset_file.write(
+                import_settings.compression,
+                import_settings.storage,
+                &mut bytes,
+            )?;
             writer.write_all(&bytes).await?;
             Ok(())
         }
@@ -123,8 +268,10 @@ impl<L: AssetLoader<Asset = TilesetImportData>> Process for TilesetImporter<L> {
 pub struct TilesetImportData {
     pub tile_size: UVec2,
     pub tile_filter: TileFilter,
-    pub tile_groups: Vec<(String, Vec<TileSourceIndex>)>,
+    pub tile_groups: Vec<(String, Vec<TileVariant>)>,
     pub sources: Vec<TilesetSource>,
+    /// Companion texture layers, which are built with the same tiles as `sources`.
+    pub layers: Vec<TilesetLayer>,
 }
 
 #[derive(Debug, Default, Serialize, Deserialize)]
@@ -133,24 +280,35 @@ pub enum TileFilter {
     All,
     None,
     List(Vec<TileSourceIndex>),
+    /// Like [`TileFilter::All`], but skips tiles where no pixel has an alpha above
+    /// `alpha_threshold`. The skipped tiles are logged, and referencing one from a group is an
+    /// error.
+    NonTransparent {
+        alpha_threshold: f32,
+    },
 }
 
 impl TileFilter {
-    fn try_for_each<E>(
-        &self,
-        sources: &[(Image, TilesetSourceFrames)],
-        f: impl FnMut(TileSourceIndex) -> Result<(), E>,
-    ) -> Result<(), E> {
+    /// Returns the tiles that pass the filter, in import order.
+    fn tile_sources(&self, sources: &[ImportSource]) -> Vec<TileSourceIndex> {
         match self {
-            Self::All => sources
+            Self::All | Self::NonTransparent { .. } => sources
                 .iter()
                 .enumerate()
-                .flat_map(|(source_id, (_, source_frames))| {
+                .flat_map(|(source_id, (_, source_frames, _))| {
                     (0..source_frames.tile_count()).map(move |tile_index| (source_id, tile_index))
                 })
-                .try_for_each(f),
-            Self::None => Ok(()),
-            Self::List(list) => list.iter().copied().try_for_each(f),
+                .collect(),
+            Self::None => Vec::new(),
+            Self::List(list) => list.clone(),
+        }
+    }
+
+    /// Returns the alpha threshold below which tiles are skipped, if any.
+    fn alpha_threshold(&self) -> Option<f32> {
+        match *self {
+            Self::NonTransparent { alpha_threshold } => Some(alpha_threshold),
+            _ => None,
         }
     }
 }
@@ -159,99 +317,466 @@ impl TileFilter {
 pub struct TilesetSource {
     pub texture: Image,
     pub layout: TilesetLayout,
+    pub padding: FramePadding,
+}
+
+/// A validated source texture, with its tile frames and padding.
+type ImportSource = (Image, TilesetSourceFrames, FramePadding);
+
+/// A companion texture layer, such as a normal map, that is imported with the same tiles as the
+/// main sources.
+#[derive(Debug)]
+pub struct TilesetLayer {
+    /// The name of the layer. Loaded tilesets label its texture as `texture_<name>`, so the name
+    /// must not be empty or a number, which would be labeled like a page of the main texture.
+    pub name: String,
+    /// Exactly one texture per source, in the same order. Each is split into tiles using the
+    /// layout and padding of its source, so it must contain every tile that is imported from
+    /// that source.
+    pub textures: Vec<Image>,
+    /// What the pixels of this layer represent.
+    pub texture_kind: TextureKind,
+    /// The texture format of this layer, which is independent of
+    /// [`TilesetImportSettings::texture_format`]. If `None`, the format of the first texture is
+    /// used.
+    pub texture_format: Option<TextureFormat>,
 }
 
 impl TilesetImportData {
-    fn import(
+    /// Builds a [`TilesetFile`] from the sources using the given settings.
+    ///
+    /// [`TilesetImportSettings::compression`] and [`TilesetImportSettings::storage`] are not used
+    /// here, and should instead be passed to [`TilesetFile::write`].
+    pub fn import(
         self,
-        mut texture_format: Option<TextureFormat>,
-        generate_mips: bool,
+        settings: &TilesetImportSettings,
     ) -> Result<TilesetFile, ImportTilesetError> {
         let TilesetImportData {
             tile_size,
             tile_filter,
             tile_groups,
             sources,
+            layers,
         } = self;
 
-        // Validate sources
-        let sources = sources
+        if let Some(layer) = layers
+            .iter()
+            .find(|layer| layer.name.is_empty() || layer.name.bytes().all(|b| b.is_ascii_digit()))
+        {
+            return Err(ImportTilesetError::LayerName(layer.name.clone()));
+        }
+        if let Some(layer) = layers
+            .iter()
+            .find(|layer| layer.textures.len() != sources.len())
+        {
+            return Err(ImportTilesetError::LayerTextureCount {
+                layer: layer.name.clone(),
+                expected: sources.len(),
+                found: layer.textures.len(),
+            });
+        }
+
+        let (sources, texture_format) = validate_sources(sources, tile_size, settings)?;
+        let texture_builder = TextureBuilder::new(tile_size, texture_format, settings)?;
+
+        // Layer textures share the frames and padding of their sources, even if the layout was
+        // detected from the source texture
+        let layers = layers
             .into_iter()
+            .map(|layer| {
+                let layer_sources = layer
+                    .textures
+                    .into_iter()
+                    .zip(&sources)
+                    .map(|(texture, (_, frames, padding))| TilesetSource {
+                        texture,
+                        layout: frames.to_layout(),
+                        padding: *padding,
+                    })
+                    .collect();
+                let layer_settings = TilesetImportSettings {
+                    texture_format: layer.texture_format,
+                    texture_kind: layer.texture_kind,
+                    premultiply_alpha: false,
+                    ..settings.clone()
+                };
+                (layer.name, layer_sources, layer_settings)
+            })
+            .collect::<Vec<_>>();
+
+        // Skip transparent tiles, checking each in parallel
+        let tile_sources = tile_filter.tile_sources(&sources);
+        let transparent = match tile_filter.alpha_threshold() {
+            Some(alpha_threshold) => {
+                map_tiles(settings.parallel, &tile_sources, &(), |_, &tile_source| {
+                    is_transparent(&sources, tile_source, alpha_threshold)
+                        .map_err(|err| ImportTilesetError::ImportTile { tile_source, err })
+                })
+            }
+            None => tile_sources.iter().map(|_| Ok(false)).collect(),
+        };
+
+        // Each job is a tile to build, along with the group that first referenced it, if any
+        let mut jobs = Vec::new();
+        let mut job_dedup = HashMap::new();
+        let mut transparent_tiles = HashSet::new();
+
+        for (tile_source, transparent) in tile_sources.into_iter().zip(transparent) {
+            if transparent? {
+                transparent_tiles.insert(tile_source);
+                continue;
+            }
+
+            let variant = TileVariant::from(tile_source);
+            job_dedup.insert(variant, jobs.len());
+            jobs.push((variant, None));
+        }
+
+        if !transparent_tiles.is_empty() {
+            let mut skipped = transparent_tiles.iter().copied().collect::<Vec<_>>();
+            skipped.sort_unstable();
+            info!(
+                "skipped {} fully transparent tiles (source, tile): {skipped:?}",
+                skipped.len()
+            );
+        }
+
+        let group_jobs = tile_groups
+            .iter()
             .enumerate()
-            .map(|(source_id, mut source)| {
-                // Check the source format
-                let source_format = source.texture.texture_descriptor.format;
-                match texture_format {
-                    None => texture_format = Some(source_format),
-                    Some(expected) => {
-                        // If the source is not in the expected format, try to convert it
-                        if expected != source_format {
-                            source.texture = source.texture.convert(expected).ok_or(
-                                ImportTilesetError::ValidateSource(SourceError::SourceFormat {
-                                    source_id,
-                                    source_format,
-                                    expected,
-                                }),
-                            )?;
+            .map(|(group, (name, tiles))| {
+                tiles
+                    .iter()
+                    .map(|&variant| match job_dedup.entry(variant) {
+                        Entry::Occupied(e) => Ok(*e.get()),
+                        Entry::Vacant(_) if transparent_tiles.contains(&variant.source) => {
+                            Err(ImportTilesetError::TransparentGroupTile {
+                                group: name.clone(),
+                                tile_source: variant.source,
+                            })
                         }
-                    }
-                }
+                        Entry::Vacant(e) => {
+                            jobs.push((variant, Some(group)));
+                            Ok(*e.insert(jobs.len() - 1))
+                        }
+                    })
+                    .collect::<Result<Vec<_>, _>>()
+            })
+            .collect::<Result<Vec<_>, ImportTilesetError>>()?;
 
-                // Get a frame accessor from the layout, texture size, and tile size
-                let frames = source
-                    .layout
-                    .tile_frames(source.texture.size(), tile_size)
-                    .map_err(|err| {
-                        ImportTilesetError::ValidateSource(SourceError::SourceLayout {
-                            source_id,
-                            err,
-                        })
-                    })?;
+        // Resolve the frame of each job up front, so that layers are trimmed to the same frames
+        let (job_frames, job_trims): (Vec<_>, Vec<_>) =
+            map_tiles(settings.parallel, &jobs, &(), |_, &(variant, group)| {
+                job_frame(&sources, variant, tile_size, settings.trim).map_err(|err| match group {
+                    Some(group) => err.in_group(&tile_groups[group].0),
+                    None => err,
+                })
+            })
+            .into_iter()
+            .collect::<Result<Vec<_>, _>>()?
+            .into_iter()
+            .unzip();
 
-                Ok((source.texture, frames))
+        // The main texture is built first, followed by each layer
+        let mut layer_names = Vec::new();
+        let mut textures = vec![(texture_builder, sources)];
+        for (name, layer_sources, layer_settings) in layers {
+            let layer = validate_sources(layer_sources, tile_size, &layer_settings)
+                .and_then(|(layer_sources, layer_format)| {
+                    check_layer_tiles(&textures[0].1, &layer_sources, &jobs)?;
+                    let builder = TextureBuilder::new(tile_size, layer_format, &layer_settings)?;
+                    Ok((builder, layer_sources))
+                })
+                .map_err(|err| err.in_layer(&name))?;
+            layer_names.push(name);
+            textures.push(layer);
+        }
+
+        // Build every tile of every texture with per-thread scratch buffers
+        let mut built = textures
+            .iter()
+            .enumerate()
+            .map(|(texture, (texture_builder, sources))| {
+                let jobs = jobs.iter().zip(&job_frames).collect::<Vec<_>>();
+                map_tiles(
+                    settings.parallel,
+                    &jobs,
+                    texture_builder,
+                    |texture_builder, &(&(variant, group), &frame)| {
+                        let err = match texture_builder.build_tile(sources, variant, frame) {
+                            Ok(tile) => return Ok(tile),
+                            Err(err) => err,
+                        };
+                        let err = match group {
+                            Some(group) => err.in_group(&tile_groups[group].0),
+                            None => err,
+                        };
+                        Err(match texture {
+                            0 => err,
+                            layer => err.in_layer(&layer_names[layer - 1]),
+                        })
+                    },
+                )
+                .into_iter()
             })
-            .collect::<Result<Vec<_>, ImportTilesetError>>()?;
+            .collect::<Vec<_>>();
 
-        // N.B., This will be set if at least one source is present. If no sources are present,
-        // then the choice of texture format is arbitrary because there can be no output tiles.
-        let texture_format = texture_format.unwrap_or(TextureFormat::Rgba8Unorm);
+        // Concatenate the tiles in order, so the output does not depend on scheduling
+        let mut assembler = TextureAssembler::new(textures.len(), settings.dedup_pixels);
+        let mut tile_trims = Vec::new();
+        let job_tiles = jobs
+            .iter()
+            .zip(job_trims)
+            .map(|(&(variant, _), trim)| {
+                let tiles = built
+                    .iter_mut()
+                    .map(|tiles| tiles.next().expect("every texture builds every job"))
+                    .collect::<Result<_, _>>()?;
+                let tile = assembler.push(variant, tiles);
 
-        let mut texture_builder = TextureBuilder::new(tile_size, texture_format, generate_mips)?;
-        let mut tile_dedup = HashMap::new();
+                // Merged duplicates keep the trim of the first occurrence
+                if let Some(trim) = trim.filter(|_| usize::from(tile) == tile_trims.len()) {
+                    tile_trims.push(trim);
+                }
+                Ok(tile)
+            })
+            .collect::<Result<Vec<_>, ImportTilesetError>>()?;
 
-        tile_filter.try_for_each(&sources, |tile_source| {
-            let tile_index = texture_builder.import_tile(&sources, tile_source)?;
-            tile_dedup.insert(tile_source, tile_index);
-            Ok(())
-        })?;
+        let merged = assembler.merged();
+        if !merged.is_empty() {
+            info!(
+                "merged {} duplicate tiles (variant, tile index): {merged:?}",
+                merged.len()
+            );
+        }
 
         let tile_groups = tile_groups
             .into_iter()
-            .map(|(name, tiles)| {
-                Ok((
-                    name.clone(),
-                    tiles
-                        .into_iter()
-                        .map(|tile_source| match tile_dedup.entry(tile_source) {
-                            Entry::Occupied(e) => Ok(*e.get()),
-                            Entry::Vacant(e) => Ok(*e.insert(
-                                texture_builder
-                                    .import_tile(&sources, tile_source)
-                                    .map_err(|err| err.in_group(&name))?,
-                            )),
-                        })
-                        .collect::<Result<_, _>>()?,
-                ))
-            })
-            .collect::<Result<Vec<_>, ImportTilesetError>>()?;
+            .zip(group_jobs)
+            .map(|((name, _), jobs)| (name, jobs.into_iter().map(|j| job_tiles[j]).collect()))
+            .collect();
+
+        let tile_count = assembler.tile_count();
+        let mut texture_data = assembler.into_data().into_iter();
+        let texture_builder = &textures[0].0;
 
         Ok(TilesetFile {
             tile_size: tile_size.into(),
-            tile_count: texture_builder.tile_count(),
+            tile_count,
             tile_groups,
             texture_format: texture_builder.texture_format(),
             texture_mips: texture_builder.mip_levels(),
-            texture_data: texture_builder.into_data(),
+            texture_data: texture_data
+                .next()
+                .expect("the main texture is always built"),
+            premultiplied_alpha: settings.premultiply_alpha,
+            layers: layer_names
+                .into_iter()
+                .zip(&textures[1..])
+                .zip(texture_data)
+                .map(|((name, (builder, _)), texture_data)| TilesetFileLayer {
+                    name,
+                    texture_format: builder.texture_format(),
+                    texture_data,
+                })
+                .collect(),
+            output_layout: settings.output_layout,
+            max_array_layers: match settings.output_layout {
+                OutputLayout::Array => settings.max_array_layers,
+                OutputLayout::Atlas { .. } => None,
+            },
+            tile_trims,
+        })
+    }
+}
+
+/// Validates the sources of a single texture, converting them to the texture format where
+/// necessary. Returns the validated sources along with the texture format.
+fn validate_sources(
+    sources: Vec<TilesetSource>,
+    tile_size: UVec2,
+    settings: &TilesetImportSettings,
+) -> Result<(Vec<ImportSource>, TextureFormat), ImportTilesetError> {
+    let TilesetImportSettings {
+        mut texture_format,
+        texture_kind,
+        ..
+    } = *settings;
+
+    if texture_kind.is_linear() {
+        texture_format = texture_format.map(|format| format.remove_srgb_suffix());
+    }
+    if texture_kind.is_two_channel() {
+        let format = *texture_format.get_or_insert(TextureFormat::Rg8Unorm);
+        if format.components() != 2 {
+            return Err(ImportTilesetError::TwoChannelFormat(format));
+        }
+    }
+
+    // Validate sources
+    let sources = sources
+        .into_iter()
+        .enumerate()
+        .map(|(source_id, mut source)| {
+            // Non-color data is never gamma-encoded, regardless of how the source was loaded
+            if texture_kind.is_linear() {
+                let format = &mut source.texture.texture_descriptor.format;
+                *format = format.remove_srgb_suffix();
+            }
+
+            // Check the source format
+            let source_format = source.texture.texture_descriptor.format;
+            match texture_format {
+                None => texture_format = Some(source_format),
+                Some(texture_format) => {
+                    // Block-compressed tiles are encoded from an uncompressed format
+                    let expected = BlockEncoder::working_format(texture_format);
+
+                    // If the source is not in the expected format, try to convert it
+                    if expected != source_format {
+                        let converted = if texture_kind.is_two_channel() {
+                            // `Image::convert` would store luminance and alpha instead
+                            convert_channels(&source.texture, expected)
+                        } else {
+                            source.texture.convert(expected)
+                        };
+                        source.texture = converted.ok_or(ImportTilesetError::ValidateSource(
+                            SourceError::SourceFormat {
+                                source_id,
+                                source_format,
+                                expected,
+                            },
+                        ))?;
+                    }
+                }
+            }
+
+            // Get a frame accessor from the layout, texture size, and tile size
+            let detect = matches!(
+                source.layout,
+                TilesetLayout::AutoDetect | TilesetLayout::DetectFrames { .. }
+            );
+            let frames = source
+                .layout
+                .tile_frames(&source.texture, tile_size)
+                .map_err(|err| {
+                    ImportTilesetError::ValidateSource(SourceError::SourceLayout { source_id, err })
+                })?;
+            match &frames {
+                _ if !detect => {}
+                TilesetSourceFrames::Grid {
+                    padding, margins, ..
+                } => info!(
+                    "detected a grid in source {source_id} with padding {padding} and margins {margins:?}"
+                ),
+                TilesetSourceFrames::Frames(detected) => {
+                    info!("detected {} frames in source {source_id}", detected.len())
+                }
+            }
+
+            Ok((source.texture, frames, source.padding))
         })
+        .collect::<Result<Vec<_>, ImportTilesetError>>()?;
+
+    // N.B., This will be set if at least one source is present. If no sources are present,
+    // then the choice of texture format is arbitrary because there can be no output tiles.
+    let texture_format = texture_format.unwrap_or(TextureFormat::Rgba8Unorm);
+
+    Ok((sources, texture_format))
+}
+
+/// Returns the frame of a tile, along with how it was trimmed if `trim` is set.
+fn job_frame(
+    sources: &[ImportSource],
+    variant: TileVariant,
+    tile_size: UVec2,
+    trim: Option<FrameTrim>,
+) -> Result<(TileFrame, Option<TileTrim>), ImportTilesetError> {
+    let tile_source = variant.source;
+    let (source, frame) = source_tile(sources, tile_source)
+        .map_err(|err| ImportTilesetError::ImportTile { tile_source, err })?;
+    let Some(trim) = trim else {
+        return Ok((frame, None));
+    };
+
+    let original_size = frame.frame.size();
+    let bounds = visible_bounds(source, frame.frame, trim.alpha_threshold).map_err(|err| {
+        ImportTilesetError::ImportTile {
+            tile_source,
+            err: SourceError::PixelAccess {
+                source_id: tile_source.0,
+                err,
+            },
+        }
+    })?;
+    let Some(bounds) = bounds else {
+        let rect = URect::from_corners(frame.anchor, frame.anchor);
+        let trim = TileTrim {
+            offset: UVec2::ZERO,
+            original_size,
+            rect: variant.transform.target_rect(rect, tile_size),
+        };
+        return Ok((frame, Some(trim)));
+    };
+
+    let offset = bounds.min - frame.frame.min;
+    let anchor = match trim.align {
+        Some(align) => align.anchor(bounds.size(), tile_size),
+        None => frame.anchor + offset,
+    };
+    let rect = URect::from_corners(anchor, anchor + bounds.size());
+    let trim = TileTrim {
+        offset,
+        original_size,
+        rect: variant.transform.target_rect(rect, tile_size),
+    };
+
+    let frame = TileFrame {
+        frame: bounds,
+        anchor,
+        align: None,
+    };
+    Ok((frame, Some(trim)))
+}
+
+/// Checks that a layer has the same frame as the main sources for every tile that is built.
+fn check_layer_tiles(
+    sources: &[ImportSource],
+    layer_sources: &[ImportSource],
+    jobs: &[(TileVariant, Option<usize>)],
+) -> Result<(), ImportTilesetError> {
+    for &(
+        TileVariant {
+            source: tile_source,
+            ..
+        },
+        _,
+    ) in jobs
+    {
+        // Tiles that are missing from the main sources are reported when they are built
+        let Ok((_, frame)) = source_tile(sources, tile_source) else {
+            continue;
+        };
+        let layer_frame = source_tile(layer_sources, tile_source).ok();
+        if layer_frame.map(|(_, layer_frame)| layer_frame) != Some(frame) {
+            return Err(ImportTilesetError::MissingLayerTile { tile_source });
+        }
+    }
+    Ok(())
+}
+
+/// Maps `f` over `items` with a clone of `scratch` per thread, or on the current thread if
+/// `parallel` is `false`.
+fn map_tiles<T: Sync, S: Clone + Sync, R: Send>(
+    parallel: bool,
+    items: &[T],
+    scratch: &S,
+    f: impl Fn(&mut S, &T) -> R + Sync,
+) -> Vec<R> {
+    if parallel {
+        par_map_init(items, || scratch.clone(), f)
+    } else {
+        let mut scratch = scratch.clone();
+        items.iter().map(|item| f(&mut scratch, item)).collect()
     }
 }
@@ -1,29 +1,93 @@
+use std::hash::BuildHasher;
+
 use bevy_asset::RenderAssetUsages;
-use bevy_color::{Color, LinearRgba};
+use bevy_color::{Alpha, Color, LinearRgba};
 use bevy_image::{Image, TextureAccessError, TextureFormatPixelInfo};
-use bevy_math::{UVec2, VectorSpace};
+use bevy_math::{URect, UVec2};
+use bevy_platform::{collections::HashMap, hash::FixedHasher};
 use wgpu_types::{Extent3d, TextureDimension, TextureFormat};
 
 use crate::{
     TileIndex, TileSourceIndex,
-    importer::{ImportTilesetError, SourceError},
-    layout::{TileFrame, TilesetSourceFrames},
+    format::OutputLayout,
+    importer::{
+        AlphaMipMode, ImportSource, ImportTilesetError, MipFilter, SourceError, TextureKind,
+        TileTransform, TileVariant, TilesetImportSettings,
+        block_encoder::BlockEncoder,
+        linear_image::{LinearImage, PixelCodec},
+        mips::{self, AlphaMix},
+    },
+    layout::{FramePadding, TileFrame},
 };
 
+/// Renders tiles into their encoded texture data.
+///
+/// The mip buffers are scratch space that is overwritten by every tile, so each thread importing
+/// tiles uses its own clone of the builder.
+#[derive(Clone)]
 pub(crate) struct TextureBuilder {
     mip_bufs: Vec<Image>,
-    texture_data: Vec<u8>,
-    tile_count: TileIndex,
     pixel_bytes: usize,
+    /// The output texture format. This differs from the format of `mip_bufs` when tiles are
+    /// block-compressed by `block_encoder`.
+    texture_format: TextureFormat,
+    block_encoder: Option<BlockEncoder>,
+    /// Whether built tiles hash their base-level pixels for deduplication.
+    dedup_pixels: bool,
+    /// How pixels are read and written when generating mipmaps.
+    mip_codec: PixelCodec,
+    mip_filter: MipFilter,
+    alpha_mix: AlphaMix,
+    texture_kind: TextureKind,
+}
+
+/// A tile built by [`TextureBuilder::build_tile`].
+pub(crate) struct BuiltTile {
+    /// A hash of the base-level pixels before block compression, if tiles are being
+    /// deduplicated by their pixel data.
+    base_hash: Option<u64>,
+    /// The encoded data of every mip level.
+    data: Vec<u8>,
 }
 
 impl TextureBuilder {
     pub fn new(
         tile_size: UVec2,
         texture_format: TextureFormat,
-        generate_mips: bool,
+        settings: &TilesetImportSettings,
     ) -> Result<Self, ImportTilesetError> {
-        let pixel_bytes = texture_format
+        let block_encoder = BlockEncoder::for_format(texture_format);
+        if block_encoder.is_some() {
+            let block_size = UVec2::from(texture_format.block_dimensions());
+            if tile_size % block_size != UVec2::ZERO {
+                return Err(ImportTilesetError::BlockAlignment {
+                    texture_format,
+                    tile_size,
+                });
+            }
+        }
+
+        if let AlphaMipMode::PreserveCoverage { alpha_test } = settings.alpha_mip_mode
+            && !(alpha_test > 0.0 && alpha_test < 1.0)
+        {
+            return Err(ImportTilesetError::AlphaTest(alpha_test));
+        }
+
+        if let OutputLayout::Atlas { columns, .. } = settings.output_layout {
+            if columns == 0 {
+                return Err(ImportTilesetError::AtlasColumns);
+            }
+            if texture_format.is_compressed() {
+                return Err(ImportTilesetError::AtlasFormat(texture_format));
+            }
+        }
+
+        if settings.max_array_layers == Some(0) {
+            return Err(ImportTilesetError::ArrayLayers);
+        }
+
+        let buf_format = BlockEncoder::working_format(texture_format);
+        let pixel_bytes = buf_format
             .pixel_size()
             .map_err(|_| ImportTilesetError::UnsupportedFormat(texture_format))?;
 
@@ -33,8 +97,10 @@ impl TextureBuilder {
             height: tile_size.y,
             depth_or_array_layers: 1,
         };
-        let mip_levels = if generate_mips {
-            base_extent.max_mips(TextureDimension::D2)
+        let mip_levels = if settings.generate_mips {
+            base_extent
+                .max_mips(TextureDimension::D2)
+                .min(settings.output_layout.max_mip_levels(tile_size))
         } else {
             1
         };
@@ -46,66 +112,92 @@ impl TextureBuilder {
                         base_extent.mip_level_size(m, TextureDimension::D2),
                         TextureDimension::D2,
                         &zero_pixel,
-                        texture_format,
+                        buf_format,
                         RenderAssetUsages::empty(),
                     )
                 })
                 .collect(),
-            texture_data: Vec::new(),
-            tile_count: 0,
             pixel_bytes,
+            texture_format,
+            block_encoder,
+            dedup_pixels: settings.dedup_pixels,
+            mip_codec: PixelCodec::for_format(buf_format),
+            mip_filter: settings.mip_filter,
+            alpha_mix: AlphaMix::new(settings),
+            texture_kind: settings.texture_kind,
         })
     }
 
     pub fn texture_format(&self) -> TextureFormat {
-        self.mip_bufs[0].texture_descriptor.format
+        self.texture_format
     }
 
     pub fn mip_levels(&self) -> u32 {
         self.mip_bufs.len() as _
     }
 
-    pub fn tile_count(&self) -> TileIndex {
-        self.tile_count
-    }
-
-    pub fn into_data(self) -> Vec<u8> {
-        self.texture_data
-    }
-
-    pub fn import_tile(
+    /// Copies, transforms, and mips a single tile, returning its encoded data.
+    ///
+    /// The `frame` of the tile must have been resolved from the sources by [`source_tile`].
+    pub fn build_tile(
         &mut self,
-        sources: &[(Image, TilesetSourceFrames)],
-        tile_source: TileSourceIndex,
-    ) -> Result<TileIndex, ImportTilesetError> {
-        self.copy_base_image(sources, tile_source)
+        sources: &[ImportSource],
+        variant: TileVariant,
+        frame: TileFrame,
+    ) -> Result<BuiltTile, ImportTilesetError> {
+        let TileVariant {
+            source: tile_source,
+            transform,
+        } = variant;
+
+        let tile_size = self.mip_bufs[0].size();
+        if transform.swaps_axes() && tile_size.x != tile_size.y {
+            return Err(ImportTilesetError::NonSquareTransform {
+                transform,
+                tile_size,
+            });
+        }
+
+        self.copy_base_image(sources, tile_source, frame)
             .map_err(|err| ImportTilesetError::ImportTile { tile_source, err })?;
+        self.transform_base_image(transform);
+        if self.alpha_mix.premultiplied {
+            self.premultiply_base_image()
+                .map_err(|err| ImportTilesetError::ImportTile {
+                    tile_source,
+                    err: SourceError::PixelAccess {
+                        source_id: tile_source.0,
+                        err,
+                    },
+                })?;
+        }
+
+        let base_hash = self.dedup_pixels.then(|| {
+            FixedHasher.hash_one(
+                self.mip_bufs[0]
+                    .data
+                    .as_ref()
+                    .expect("images are initialized"),
+            )
+        });
+
         self.generate_mips()
             .map_err(ImportTilesetError::GenerateMips)?;
-        self.write_mip_bufs();
 
-        let tile_index = self.tile_count;
-        self.tile_count += 1;
-        Ok(tile_index)
+        Ok(BuiltTile {
+            base_hash,
+            data: self.encode_mip_bufs(),
+        })
     }
 
     fn copy_base_image(
         &mut self,
-        sources: &[(Image, TilesetSourceFrames)],
-        (source_id, tile_index): TileSourceIndex,
+        sources: &[ImportSource],
+        tile_source: TileSourceIndex,
+        TileFrame { frame, anchor, .. }: TileFrame,
     ) -> Result<(), SourceError> {
-        if source_id >= sources.len() {
-            return Err(SourceError::SourceOutOfRange {
-                source_id,
-                source_len: sources.len(),
-            });
-        }
-
-        // Get the source image and tile frame
-        let (source, source_frames) = &sources[source_id];
-        let TileFrame { frame, anchor } = source_frames
-            .get(tile_index)
-            .map_err(|err| SourceError::SourceLayout { source_id, err })?;
+        let (source, _, padding) = &sources[tile_source.0];
+        let padding = *padding;
 
         // Parameters for indexing into the pixel buffers
         let frame_size = frame.size();
@@ -115,13 +207,36 @@ impl TextureBuilder {
         let src_row_bytes = src_size.x as usize * self.pixel_bytes;
         let src_data = source.data.as_ref().expect("images are initialized");
 
+        // The pixels outside the frame may still hold the previous tile
         let tgt_size = self.mip_bufs[0].size();
+        let padded = frame_size != tgt_size;
+        let fill_pixel = match padding {
+            _ if !padded => None,
+            FramePadding::Transparent => Some(vec![0; self.pixel_bytes]),
+            FramePadding::Solid(color) => Some(
+                solid_pixel(self.mip_bufs[0].texture_descriptor.format, color).map_err(|err| {
+                    SourceError::PixelAccess {
+                        source_id: tile_source.0,
+                        err,
+                    }
+                })?,
+            ),
+            // Every pixel is overwritten once the frame has been copied
+            FramePadding::Extrude => None,
+        };
+
         let tgt_row_bytes = tgt_size.x as usize * self.pixel_bytes;
         let tgt_data = self.mip_bufs[0]
             .data
             .as_mut()
             .expect("images are initialized");
 
+        if let Some(fill_pixel) = fill_pixel {
+            for tgt_pixel in tgt_data.chunks_exact_mut(self.pixel_bytes) {
+                tgt_pixel.copy_from_slice(&fill_pixel);
+            }
+        }
+
         // Index of the top-left pixel in the source and tile images
         let mut src_i = (frame.min.x + frame.min.y * src_size.x) as usize * self.pixel_bytes;
         let mut tgt_i = (anchor.x + anchor.y * tgt_size.x) as usize * self.pixel_bytes;
@@ -137,137 +252,268 @@ impl TextureBuilder {
             tgt_i += tgt_row_bytes;
         }
 
+        if padded && padding == FramePadding::Extrude {
+            extrude_frame(
+                tgt_data,
+                tgt_size,
+                URect::from_corners(anchor, anchor + frame_size),
+                self.pixel_bytes,
+            );
+        }
+
         Ok(())
     }
 
-    fn generate_mips(&mut self) -> Result<(), TextureAccessError> {
-        for m in 1..self.mip_bufs.len() {
-            let (a, b) = self.mip_bufs.split_at_mut(m);
+    fn transform_base_image(&mut self, transform: TileTransform) {
+        if transform == TileTransform::Identity {
+            return;
+        }
+
+        let size = self.mip_bufs[0].size();
+        let data = self.mip_bufs[0]
+            .data
+            .as_mut()
+            .expect("images are initialized");
+        let src_data = data.clone();
 
-            let src = &a[m - 1];
-            let tgt = &mut b[0];
+        let pixel_bytes = self.pixel_bytes;
+        let pixel_index = |p: UVec2| (p.x + p.y * size.x) as usize * pixel_bytes;
 
-            if src.size() == 2 * tgt.size() {
-                downscale_image_half(src, tgt)?;
-            } else {
-                downscale_image_bilinear(src, tgt)?;
+        for y in 0..size.y {
+            for x in 0..size.x {
+                let tgt_i = pixel_index(UVec2::new(x, y));
+                let src_i = pixel_index(transform.source_pixel(UVec2::new(x, y), size));
+                data[tgt_i..tgt_i + pixel_bytes]
+                    .copy_from_slice(&src_data[src_i..src_i + pixel_bytes]);
             }
         }
-        Ok(())
     }
 
-    fn write_mip_bufs(&mut self) {
-        for image in &self.mip_bufs {
-            self.texture_data
-                .extend_from_slice(image.data.as_ref().expect("images are initialized"));
+    /// Multiplies the color channels of the base image by its alpha, in linear space.
+    fn premultiply_base_image(&mut self) -> Result<(), TextureAccessError> {
+        let mut image = self.mip_codec.decode(&self.mip_bufs[0])?;
+        for pixel in image.pixels_mut() {
+            *pixel = LinearRgba {
+                alpha: pixel.alpha,
+                ..*pixel * pixel.alpha
+            };
         }
+        self.mip_codec.encode(&image, &mut self.mip_bufs[0])
     }
-}
 
-/// Downscales `src` into `tgt` by blending 2x2 blocks of pixels.
-fn downscale_image_half(src: &Image, tgt: &mut Image) -> Result<(), TextureAccessError> {
-    debug_assert_eq!(src.size(), tgt.size() * 2);
+    fn generate_mips(&mut self) -> Result<(), TextureAccessError> {
+        mips::generate_mips(
+            &mut self.mip_bufs,
+            self.mip_codec,
+            self.mip_filter,
+            self.alpha_mix,
+            self.texture_kind,
+        )
+    }
 
-    for ty in 0..tgt.height() {
-        for tx in 0..tgt.width() {
-            let sx = 2 * tx;
-            let sy = 2 * ty;
+    fn encode_mip_bufs(&self) -> Vec<u8> {
+        let mut data = Vec::new();
+        for image in &self.mip_bufs {
+            let pixels = image.data.as_ref().expect("images are initialized");
+            match self.block_encoder {
+                Some(encoder) => encoder.encode(pixels, image.size(), &mut data),
+                None => data.extend_from_slice(pixels),
+            }
+        }
+        data
+    }
+}
 
-            let mix_color = alpha_discard_mix(&[
-                src.get_color_at(sx, sy)?,
-                src.get_color_at(sx + 1, sy)?,
-                src.get_color_at(sx, sy + 1)?,
-                src.get_color_at(sx + 1, sy + 1)?,
-            ]);
+/// Concatenates built tiles into the texture data of a tileset and its layers, in the order they
+/// are added.
+pub(crate) struct TextureAssembler {
+    /// The texture data of the main texture, followed by each layer.
+    texture_data: Vec<Vec<u8>>,
+    tile_count: TileIndex,
+    /// The tile indices with each hash of their base-level images, if tiles are being
+    /// deduplicated by their pixel data.
+    pixel_dedup: Option<HashMap<u64, Vec<TileIndex>>>,
+    /// Each tile that was merged into an earlier tile, along with the index of that tile.
+    merged: Vec<(TileVariant, TileIndex)>,
+}
 
-            tgt.set_color_at(tx, ty, mix_color)?;
+impl TextureAssembler {
+    pub fn new(texture_count: usize, dedup_pixels: bool) -> Self {
+        Self {
+            texture_data: vec![Vec::new(); texture_count],
+            tile_count: 0,
+            pixel_dedup: dedup_pixels.then(HashMap::new),
+            merged: Vec::new(),
         }
     }
 
-    Ok(())
-}
+    pub fn tile_count(&self) -> TileIndex {
+        self.tile_count
+    }
 
-/// Downscales `src` into `tgt` using bilinear interpolation.
-fn downscale_image_bilinear(src: &Image, tgt: &mut Image) -> Result<(), TextureAccessError> {
-    let scale = src.size().as_vec2() / tgt.size().as_vec2();
+    pub fn merged(&self) -> &[(TileVariant, TileIndex)] {
+        &self.merged
+    }
 
-    for ty in 0..tgt.height() {
-        for tx in 0..tgt.width() {
-            let sxy_f = scale * UVec2::new(tx, ty).as_vec2();
-            let sxy_i = sxy_f.floor();
+    pub fn into_data(self) -> Vec<Vec<u8>> {
+        self.texture_data
+    }
 
-            let t = sxy_f - sxy_i;
-            let sx = sxy_i.x as u32;
-            let sy = sxy_i.y as u32;
+    /// Appends a tile, built once for each texture, and returns its index. If an earlier tile has
+    /// identical pixels in every texture, its index is returned instead.
+    pub fn push(&mut self, variant: TileVariant, tiles: Vec<BuiltTile>) -> TileIndex {
+        let hashes = tiles
+            .iter()
+            .map(|tile| tile.base_hash)
+            .collect::<Option<Vec<_>>>();
+        if let (Some(pixel_dedup), Some(hashes)) = (&mut self.pixel_dedup, hashes) {
+            let candidates = pixel_dedup.entry(FixedHasher.hash_one(hashes)).or_default();
+
+            // Hashes can collide, so the tile must also match the data built for an earlier tile
+            let texture_data = &self.texture_data;
+            let identical = candidates.iter().copied().find(|&tile_index| {
+                texture_data.iter().zip(&tiles).all(|(data, tile)| {
+                    let start = usize::from(tile_index) * tile.data.len();
+                    data[start..start + tile.data.len()] == tile.data
+                })
+            });
 
-            let c0 = alpha_discard_lerp(
-                src.get_color_at(sx, sy)?,
-                src.get_color_at(sx + 1, sy)?,
-                t.x,
-            );
-            let c1 = alpha_discard_lerp(
-                src.get_color_at(sx, sy + 1)?,
-                src.get_color_at(sx + 1, sy + 1)?,
-                t.x,
-            );
-            let mix_color = alpha_discard_lerp(c0, c1, t.y);
+            if let Some(tile_index) = identical {
+                self.merged.push((variant, tile_index));
+                return tile_index;
+            }
+            candidates.push(self.tile_count);
+        }
 
-            tgt.set_color_at(tx, ty, mix_color)?;
+        for (texture_data, tile) in self.texture_data.iter_mut().zip(tiles) {
+            texture_data.extend_from_slice(&tile.data);
         }
-    }
 
-    Ok(())
+        let tile_index = self.tile_count;
+        self.tile_count += 1;
+        tile_index
+    }
 }
 
-/// Returns `true` if `alpha` is below the discard threshold.
-fn should_discard(alpha: f32) -> bool {
-    const ALPHA_CUTOFF: f32 = 1e-4;
-    alpha <= ALPHA_CUTOFF
+/// Encodes a single pixel of `color` in the given format.
+fn solid_pixel(format: TextureFormat, color: Color) -> Result<Vec<u8>, TextureAccessError> {
+    let mut image = Image::new_fill(
+        Extent3d::default(),
+        TextureDimension::D2,
+        &vec![0; format.pixel_size()?],
+        format,
+        RenderAssetUsages::empty(),
+    );
+    match PixelCodec::for_format(format) {
+        // Packed formats are not supported by `set_color_at`
+        codec @ PixelCodec::Packed(_) => {
+            let mut pixel = LinearImage::new(UVec2::ONE);
+            pixel.set(0, 0, color.to_linear());
+            codec.encode(&pixel, &mut image)?;
+        }
+        _ => image.set_color_at(0, 0, color)?,
+    }
+    Ok(image.data.expect("images are initialized"))
 }
 
-/// Mixes a slice of colors, disregarding transparent elements.
-fn alpha_discard_mix(colors: &[Color]) -> Color {
-    let mut n = 0;
-    let mut linear_sum = LinearRgba::NONE;
-    for color in colors {
-        let linear = color.to_linear();
-        if should_discard(linear.alpha) {
-            // continue;
-            // TODO: Figure out why tile borders are flickering if we don't toss everything.
-            return LinearRgba::NONE.into();
+/// Fills the pixels of `data` outside of `frame` by repeating the nearest pixel inside it. An
+/// empty frame has no pixels to repeat, so the whole tile is left transparent.
+fn extrude_frame(data: &mut [u8], size: UVec2, frame: URect, pixel_bytes: usize) {
+    if frame.is_empty() {
+        data.fill(0);
+        return;
+    }
+
+    let row_bytes = size.x as usize * pixel_bytes;
+    let pixel_index = |x: u32, y: u32| y as usize * row_bytes + x as usize * pixel_bytes;
+
+    // Extend each row of the frame to the left and right edges
+    for y in frame.min.y..frame.max.y {
+        let left = pixel_index(frame.min.x, y);
+        for x in 0..frame.min.x {
+            data.copy_within(left..left + pixel_bytes, pixel_index(x, y));
         }
 
-        n += 1;
-        linear_sum += linear;
+        let right = pixel_index(frame.max.x - 1, y);
+        for x in frame.max.x..size.x {
+            data.copy_within(right..right + pixel_bytes, pixel_index(x, y));
+        }
     }
 
-    if n > 1 {
-        linear_sum /= n as f32;
+    // Extend the first and last rows to the top and bottom edges
+    let top = pixel_index(0, frame.min.y);
+    for y in 0..frame.min.y {
+        data.copy_within(top..top + row_bytes, pixel_index(0, y));
     }
 
-    if should_discard(linear_sum.alpha) {
-        LinearRgba::NONE
-    } else {
-        linear_sum
+    let bottom = pixel_index(0, frame.max.y - 1);
+    for y in frame.max.y..size.y {
+        data.copy_within(bottom..bottom + row_bytes, pixel_index(0, y));
     }
-    .into()
 }
 
-/// Interpolates between two colors, disregarding transparent elements.
-fn alpha_discard_lerp(a: Color, b: Color, t: f32) -> Color {
-    let a_lin = a.to_linear();
-    if should_discard(a_lin.alpha) {
-        // return b;
-        // TODO: Figure out why tile borders are flickering if we don't toss everything.
-        return LinearRgba::NONE.into();
+/// Gets the source image and tile frame of a tile.
+pub(crate) fn source_tile(
+    sources: &[ImportSource],
+    (source_id, tile_index): TileSourceIndex,
+) -> Result<(&Image, TileFrame), SourceError> {
+    let (source, source_frames, _) =
+        sources
+            .get(source_id)
+            .ok_or(SourceError::SourceOutOfRange {
+                source_id,
+                source_len: sources.len(),
+            })?;
+
+    let frame = source_frames
+        .get(tile_index)
+        .map_err(|err| SourceError::SourceLayout { source_id, err })?;
+
+    Ok((source, frame))
+}
+
+/// Returns the bounding box of the pixels in `frame` with an alpha above `alpha_threshold`, or
+/// `None` if there are none.
+pub(crate) fn visible_bounds(
+    source: &Image,
+    frame: URect,
+    alpha_threshold: f32,
+) -> Result<Option<URect>, TextureAccessError> {
+    let mut bounds: Option<URect> = None;
+    for y in frame.min.y..frame.max.y {
+        for x in frame.min.x..frame.max.x {
+            if source.get_color_at(x, y)?.alpha() > alpha_threshold {
+                let pixel = URect::new(x, y, x + 1, y + 1);
+                bounds = Some(bounds.map_or(pixel, |bounds| bounds.union(pixel)));
+            }
+        }
     }
+    Ok(bounds)
+}
 
-    let b_lin = b.to_linear();
-    if should_discard(b_lin.alpha) {
-        // return a;
-        // TODO: Figure out why tile borders are flickering if we don't toss everything.
-        return LinearRgba::NONE.into();
+/// Returns `true` if no pixel in the tile's frame has an alpha above `alpha_threshold`.
+pub(crate) fn is_transparent(
+    sources: &[ImportSource],
+    tile_source: TileSourceIndex,
+    alpha_threshold: f32,
+) -> Result<bool, SourceError> {
+    let (source, TileFrame { frame, .. }) = source_tile(sources, tile_source)?;
+
+    for y in frame.min.y..frame.max.y {
+        for x in frame.min.x..frame.max.x {
+            let alpha = source
+                .get_color_at(x, y)
+                .map_err(|err| SourceError::PixelAccess {
+                    source_id: tile_source.0,
+                    err,
+                })?
+                .alpha();
+
+            if alpha > alpha_threshold {
+                return Ok(false);
+            }
+        }
     }
 
-    a_lin.lerp(b_lin, t).into()
+    Ok(true)
 }
@@ -0,0 +1,143 @@
+use std::fmt;
+
+use bevy_math::{URect, UVec2};
+use serde::{
+    Deserialize, Deserializer, Serialize, Serializer,
+    de::{self, SeqAccess, Visitor},
+    ser::SerializeTuple,
+};
+
+use crate::{TileIndex, TileSourceIndex};
+
+/// A flip or rotation applied to a tile when it is imported.
+///
+/// Rotations are clockwise. Transforms that swap the axes of the tile (rotating by 90 or 270
+/// degrees, or transposing) require square tiles.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum TileTransform {
+    #[default]
+    Identity,
+    FlipX,
+    FlipY,
+    Rot90,
+    Rot180,
+    Rot270,
+    /// Mirrors the tile across the diagonal from its top-left to bottom-right corner.
+    Transpose,
+    /// Mirrors the tile across the diagonal from its top-right to bottom-left corner.
+    AntiTranspose,
+}
+
+impl TileTransform {
+    /// Returns `true` if the transform swaps the width and height of the tile.
+    pub fn swaps_axes(self) -> bool {
+        matches!(
+            self,
+            Self::Rot90 | Self::Rot270 | Self::Transpose | Self::AntiTranspose
+        )
+    }
+
+    /// Returns the transform that undoes this one.
+    pub fn inverse(self) -> Self {
+        match self {
+            Self::Rot90 => Self::Rot270,
+            Self::Rot270 => Self::Rot90,
+            other => other,
+        }
+    }
+
+    /// Returns the rect covered by `rect` once transformed, for a rect within a tile of `size`.
+    /// An empty rect stays empty, and is moved along with its corners.
+    pub(crate) fn target_rect(self, rect: URect, size: UVec2) -> URect {
+        // Corners lie between pixels, so they are mirrored about the edges of the tile rather
+        // than about its last pixels
+        let inverse = self.inverse();
+        let a = inverse.source_pixel(rect.min, size + 1);
+        let b = inverse.source_pixel(rect.max, size + 1);
+        URect::from_corners(a, b)
+    }
+
+    /// Returns the pixel that is moved to `target` by the transform, for a tile of `size`.
+    pub(crate) fn source_pixel(self, target: UVec2, size: UVec2) -> UVec2 {
+        let UVec2 { x, y } = target;
+        let max = size - 1;
+        match self {
+            Self::Identity => target,
+            Self::FlipX => UVec2::new(max.x - x, y),
+            Self::FlipY => UVec2::new(x, max.y - y),
+            Self::Rot90 => UVec2::new(y, max.y - x),
+            Self::Rot180 => max - target,
+            Self::Rot270 => UVec2::new(max.x - y, x),
+            Self::Transpose => UVec2::new(y, x),
+            Self::AntiTranspose => UVec2::new(max.x - y, max.y - x),
+        }
+    }
+}
+
+/// A reference to a source tile, with a transform applied.
+///
+/// In `.ts.ron` files this is written as `(source_id, tile_index)` for an untransformed tile, or
+/// `(source_id, tile_index, transform)`, e.g. `(0, 3, FlipX)`. Each distinct variant of a tile is
+/// stored as its own layer.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TileVariant {
+    pub source: TileSourceIndex,
+    pub transform: TileTransform,
+}
+
+impl TileVariant {
+    pub fn new(source: TileSourceIndex, transform: TileTransform) -> Self {
+        Self { source, transform }
+    }
+}
+
+impl From<TileSourceIndex> for TileVariant {
+    fn from(source: TileSourceIndex) -> Self {
+        Self::new(source, TileTransform::Identity)
+    }
+}
+
+impl Serialize for TileVariant {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let (source_id, tile_index) = self.source;
+        if self.transform == TileTransform::Identity {
+            (source_id, tile_index).serialize(serializer)
+        } else {
+            let mut tuple = serializer.serialize_tuple(3)?;
+            tuple.serialize_element(&source_id)?;
+            tuple.serialize_element(&tile_index)?;
+            tuple.serialize_element(&self.transform)?;
+            tuple.end()
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for TileVariant {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct TileVariantVisitor;
+
+        impl<'de> Visitor<'de> for TileVariantVisitor {
+            type Value = TileVariant;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str(
+                    "a tuple of (source_id, tile_index) or (source_id, tile_index, transform)",
+                )
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+                let source_id: usize = seq
+                    .next_element()?
+                    .ok_or_else(|| de::Error::invalid_length(0, &self))?;
+                let tile_index: TileIndex = seq
+                    .next_element()?
+                    .ok_or_else(|| de::Error::invalid_length(1, &self))?;
+                let transform = seq.next_element()?.unwrap_or_default();
+
+                Ok(TileVariant::new((source_id, tile_index), transform))
+            }
+        }
+
+        deserializer.deserialize_tuple(3, TileVariantVisitor)
+    }
+}
@@ -0,0 +1,202 @@
+use bevy_color::{Alpha, Color};
+use bevy_image::Image;
+use bevy_math::{URect, UVec2};
+
+use super::{FrameAlign, LayoutError, TileFrame};
+
+/// Infers the padding and margins of a grid of `tile_size` tiles from the separator lines of an
+/// image. See [`TilesetLayout::AutoDetect`](super::TilesetLayout::AutoDetect).
+pub(super) fn detect_grid(image: &Image, tile_size: UVec2) -> Result<(UVec2, URect), LayoutError> {
+    let (columns, rows) = separator_lines(image)?;
+    let not_found = || LayoutError::DetectGrid {
+        image_size: image.size(),
+        tile_size,
+    };
+
+    let (padding_x, left, right) = detect_axis(&columns, tile_size.x).ok_or_else(not_found)?;
+    let (padding_y, top, bottom) = detect_axis(&rows, tile_size.y).ok_or_else(not_found)?;
+
+    // Grid margins are offset by `min`, and their total on each axis is the size of the rect
+    let min = UVec2::new(left, top);
+    let margins = URect {
+        min,
+        max: min + UVec2::new(left + right, top + bottom),
+    };
+    Ok((UVec2::new(padding_x, padding_y), margins))
+}
+
+/// Returns whether each column and each row of the image is a separator line, where every pixel
+/// is either fully transparent or the same color.
+fn separator_lines(image: &Image) -> Result<(Vec<bool>, Vec<bool>), LayoutError> {
+    let size = image.size();
+    let pixel = |x, y| image.get_color_at(x, y).map_err(LayoutError::PixelAccess);
+
+    // The first pixel of each column, and whether the column is still transparent or uniform
+    let mut columns = (0..size.x)
+        .map(|x| Ok(Line::new(pixel(x, 0)?)))
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut rows = Vec::with_capacity(size.y as usize);
+
+    for y in 0..size.y {
+        let mut row = Line::new(pixel(0, y)?);
+        for (x, column) in (0..size.x).zip(&mut columns) {
+            let color = pixel(x, y)?;
+            row.push(color);
+            column.push(color);
+        }
+        rows.push(row.is_separator());
+    }
+
+    let columns = columns.iter().map(Line::is_separator).collect();
+    Ok((columns, rows))
+}
+
+/// Tracks whether a line of pixels is a separator as its pixels are visited.
+struct Line {
+    first: Color,
+    transparent: bool,
+    uniform: bool,
+}
+
+impl Line {
+    fn new(first: Color) -> Self {
+        Self {
+            first,
+            transparent: true,
+            uniform: true,
+        }
+    }
+
+    fn push(&mut self, color: Color) {
+        self.transparent &= color.alpha() == 0.0;
+        self.uniform &= color == self.first;
+    }
+
+    fn is_separator(&self) -> bool {
+        self.transparent || self.uniform
+    }
+}
+
+/// Finds the padding and the margins at either end of a single axis, such that every line
+/// outside of the tiles is a separator.
+///
+/// Of the layouts that fit, the one with the most tiles is chosen, followed by the widest padding
+/// and then the smallest leading margin. Padding and leading margins are assumed to be smaller
+/// than a tile.
+fn detect_axis(separators: &[bool], tile: u32) -> Option<(u32, u32, u32)> {
+    let len = separators.len() as u32;
+
+    // The number of non-separator lines before each line, so any range can be checked at once
+    let mut content = vec![0; separators.len() + 1];
+    for (i, &separator) in separators.iter().enumerate() {
+        content[i + 1] = content[i] + u32::from(!separator);
+    }
+    let is_gap = |start: u32, end: u32| content[start as usize] == content[end as usize];
+
+    let mut best: Option<(u32, u32, u32, u32)> = None;
+    for padding in 0..tile {
+        for start in 0..tile.min(len) {
+            // Padding is meaningless without a gap between two tiles
+            let count = (len - start + padding) / (tile + padding);
+            if count == 0 || (count == 1 && padding > 0) {
+                continue;
+            }
+            let end = len - start - count * tile - (count - 1) * padding;
+
+            let fits = is_gap(0, start)
+                && is_gap(len - end, len)
+                && (1..count).all(|i| {
+                    let gap = start + i * (tile + padding) - padding;
+                    is_gap(gap, gap + padding)
+                });
+            if fits && best.is_none_or(|(c, p, ..)| (count, padding) > (c, p)) {
+                best = Some((count, padding, start, end));
+            }
+        }
+    }
+
+    best.map(|(_, padding, start, end)| (padding, start, end))
+}
+
+/// Finds a frame around each island of opaque pixels in an image. See
+/// [`TilesetLayout::DetectFrames`](super::TilesetLayout::DetectFrames).
+pub(super) fn detect_frames(
+    image: &Image,
+    tile_size: UVec2,
+    alpha_threshold: f32,
+    min_size: UVec2,
+    align: FrameAlign,
+) -> Result<Vec<TileFrame>, LayoutError> {
+    let size = image.size();
+    let (width, height) = (size.x as usize, size.y as usize);
+
+    let mut opaque = Vec::with_capacity(width * height);
+    for y in 0..size.y {
+        for x in 0..size.x {
+            let color = image.get_color_at(x, y).map_err(LayoutError::PixelAccess)?;
+            opaque.push(color.alpha() > alpha_threshold);
+        }
+    }
+
+    // Flood fill each island in turn, clearing its pixels as they are visited
+    let mut islands = Vec::new();
+    let mut stack = Vec::new();
+    for start in 0..opaque.len() {
+        if !opaque[start] {
+            continue;
+        }
+        opaque[start] = false;
+        stack.push(start);
+
+        let (mut min, mut max) = (UVec2::MAX, UVec2::ZERO);
+        while let Some(i) = stack.pop() {
+            let (x, y) = (i % width, i / width);
+            let pixel = UVec2::new(x as u32, y as u32);
+            (min, max) = (min.min(pixel), max.max(pixel + 1));
+
+            for ny in y.saturating_sub(1)..(y + 2).min(height) {
+                for nx in x.saturating_sub(1)..(x + 2).min(width) {
+                    let n = ny * width + nx;
+                    if opaque[n] {
+                        opaque[n] = false;
+                        stack.push(n);
+                    }
+                }
+            }
+        }
+
+        let island = URect { min, max };
+        if island.size().cmpge(min_size).all() {
+            islands.push(island);
+        }
+    }
+
+    // Islands are found in order of their top edge, so each row starts with its highest island
+    let mut rows: Vec<(u32, Vec<URect>)> = Vec::new();
+    for island in islands {
+        match rows.last_mut() {
+            Some((bottom, row)) if island.min.y < *bottom => {
+                *bottom = (*bottom).max(island.max.y);
+                row.push(island);
+            }
+            _ => rows.push((island.max.y, vec![island])),
+        }
+    }
+
+    rows.into_iter()
+        .flat_map(|(_, mut row)| {
+            row.sort_by_key(|island| island.min.x);
+            row
+        })
+        .map(|frame| {
+            if frame.size().cmpgt(tile_size).any() {
+                return Err(LayoutError::FrameTooLarge { frame, tile_size });
+            }
+            Ok(TileFrame {
+                frame,
+                anchor: UVec2::ZERO,
+                align: Some(align),
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,390 @@
+use bevy_color::Color;
+use bevy_image::{Image, TextureAccessError};
+use bevy_math::{URect, UVec2, Vec2};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::TileIndex;
+
+mod detect;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct TileFrame {
+    pub frame: URect,
+    #[serde(default)]
+    pub anchor: UVec2,
+    /// If set, `anchor` is replaced by the anchor that aligns the frame within the tile, once
+    /// the tile size is known.
+    #[serde(default)]
+    pub align: Option<FrameAlign>,
+}
+
+impl TileFrame {
+    pub const fn from_tile_size(tile_size: UVec2) -> Self {
+        Self {
+            frame: URect {
+                min: UVec2::ZERO,
+                max: tile_size,
+            },
+            anchor: UVec2::ZERO,
+            align: None,
+        }
+    }
+
+    /// Returns the frame with its anchor computed from `align`, if set.
+    pub fn resolve(self, tile_size: UVec2) -> Self {
+        match self.align {
+            Some(align) => Self {
+                anchor: align.anchor(self.frame.size(), tile_size),
+                align: None,
+                ..self
+            },
+            None => self,
+        }
+    }
+
+    /// Returns `true` if the frame is not empty, lies within the image, and fits within a tile
+    /// once offset by its anchor. The frame may touch the edges of either.
+    pub fn is_valid(&self, image_size: UVec2, tile_size: UVec2) -> bool {
+        self.frame.min.cmplt(self.frame.max).all()
+            && self.frame.max.cmple(image_size).all()
+            && (self.frame.size() + self.anchor).cmple(tile_size).all()
+    }
+}
+
+/// Where a frame is placed within its tile, when the anchor is computed rather than given.
+///
+/// Where the free space cannot be split evenly, frames are placed a pixel towards the top left.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum FrameAlign {
+    /// Centered on both axes.
+    #[default]
+    Center,
+    /// Centered horizontally, and touching the bottom edge of the tile.
+    BottomCenter,
+    /// Touching the top and left edges of the tile, which is the same as a zero anchor.
+    TopLeft,
+    /// Placed so that the point at this fraction of the frame size lies at the same fraction of
+    /// the tile size. `Pivot(0.5, 1.0)` is the same as [`FrameAlign::BottomCenter`]. Values are
+    /// clamped to `[0, 1]`.
+    Pivot(f32, f32),
+}
+
+impl FrameAlign {
+    /// Returns the anchor that places a frame of the given size within a tile.
+    pub fn anchor(self, frame_size: UVec2, tile_size: UVec2) -> UVec2 {
+        let space = tile_size.saturating_sub(frame_size);
+        match self {
+            Self::Center => space / 2,
+            Self::BottomCenter => UVec2::new(space.x / 2, space.y),
+            Self::TopLeft => UVec2::ZERO,
+            Self::Pivot(x, y) => {
+                let pivot = Vec2::new(x, y).clamp(Vec2::ZERO, Vec2::ONE);
+                (space.as_vec2() * pivot).floor().as_uvec2()
+            }
+        }
+    }
+}
+
+/// How the pixels of a tile that lie outside its [`TileFrame`] are filled.
+///
+/// Transparent padding can cause dark fringes once mipmaps are generated or the texture is
+/// sampled with linear filtering, which extruding the frame edges avoids.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum FramePadding {
+    /// Fill with transparent black.
+    #[default]
+    Transparent,
+    /// Repeat the nearest pixel on the edge of the frame.
+    Extrude,
+    /// Fill with a solid color.
+    Solid(Color),
+}
+
+/// The order in which the tiles of a grid are numbered.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GridOrder {
+    /// Left to right along each row, from the top row down.
+    #[default]
+    RowMajor,
+    /// Top to bottom along each column, from the left column across.
+    ColumnMajor,
+}
+
+#[derive(Debug, Clone)]
+pub enum TilesetLayout {
+    /// A grid of tiles, numbered according to `order`.
+    ///
+    /// If `region` is set, only that part of the image is split into tiles, with `margins`
+    /// relative to it. This allows independent blocks of tiles in the same image to be declared
+    /// as separate sources.
+    Grid {
+        padding: UVec2,
+        margins: URect,
+        order: GridOrder,
+        region: Option<URect>,
+    },
+    Frames(Vec<TileFrame>),
+    /// A grid whose padding and margins are inferred from the source image, by finding the rows
+    /// and columns between tiles that are fully transparent or a single color.
+    ///
+    /// Of the grids that fit, the one with the most tiles is used, followed by the widest padding.
+    /// Padding and the left and top margins are assumed to be smaller than a tile.
+    AutoDetect,
+    /// Frames around each island of connected pixels with an alpha above `alpha_threshold`,
+    /// including diagonal neighbours. Islands smaller than `min_size` on either axis are ignored.
+    ///
+    /// Frames are numbered row by row, where islands that overlap vertically share a row, and
+    /// each is placed in its tile according to `align`. An island larger than the tile is an
+    /// error.
+    DetectFrames {
+        alpha_threshold: f32,
+        min_size: UVec2,
+        align: FrameAlign,
+    },
+}
+
+impl TilesetLayout {
+    pub const fn unpadded_grid() -> Self {
+        Self::Grid {
+            padding: UVec2::ZERO,
+            margins: URect {
+                min: UVec2::new(0, 0),
+                max: UVec2::new(0, 0),
+            },
+            order: GridOrder::RowMajor,
+            region: None,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum TilesetSourceFrames {
+    Grid {
+        tile_count: TileIndex,
+        tile_size: UVec2,
+        grid_size: UVec2,
+        padding: UVec2,
+        margins: URect,
+        order: GridOrder,
+        region: Option<URect>,
+    },
+    Frames(Vec<TileFrame>),
+}
+
+#[derive(Debug, Error)]
+pub enum LayoutError {
+    #[error(
+        "source image size {image_size} cannot be split into tiles of size {tile_size} with padding {padding} and margins {margins:?}"
+    )]
+    InvalidGrid {
+        image_size: UVec2,
+        tile_size: UVec2,
+        padding: UVec2,
+        margins: URect,
+    },
+    #[error(
+        "grid region {region:?} is inverted or does not fit within source image size {image_size}"
+    )]
+    InvalidRegion { image_size: UVec2, region: URect },
+    #[error(
+        "frame {frame:?} is not compatible with source image size {image_size} and tile size {tile_size}"
+    )]
+    InvalidFrame {
+        image_size: UVec2,
+        tile_size: UVec2,
+        frame: TileFrame,
+    },
+    #[error(
+        "the source layout defines {count} tiles, but the maximum index is {}",
+        TileIndex::MAX
+    )]
+    TooManyTiles { count: usize },
+    #[error("tile index was {idx}, but the source contains {max} tiles")]
+    OutOfRange { idx: TileIndex, max: TileIndex },
+    #[error(
+        "no grid of tiles with size {tile_size} separated by transparent or uniform lines was found in source image of size {image_size}"
+    )]
+    DetectGrid { image_size: UVec2, tile_size: UVec2 },
+    #[error("detected frame {frame:?} has size {}, which is larger than the tile size {tile_size}", frame.size())]
+    FrameTooLarge { frame: URect, tile_size: UVec2 },
+    #[error("source pixels could not be read: {0}")]
+    PixelAccess(#[source] TextureAccessError),
+}
+
+impl TilesetLayout {
+    pub fn tile_frames(
+        self,
+        image: &Image,
+        tile_size: UVec2,
+    ) -> Result<TilesetSourceFrames, LayoutError> {
+        let image_size = image.size();
+        match self {
+            Self::Grid {
+                padding,
+                margins,
+                order,
+                region,
+            } => Self::grid_tile_frames(image_size, tile_size, padding, margins, order, region),
+            Self::Frames(frames) => Self::frames_tile_frames(image_size, tile_size, frames),
+            Self::AutoDetect => {
+                let (padding, margins) = detect::detect_grid(image, tile_size)?;
+                let order = GridOrder::RowMajor;
+                Self::grid_tile_frames(image_size, tile_size, padding, margins, order, None)
+            }
+            Self::DetectFrames {
+                alpha_threshold,
+                min_size,
+                align,
+            } => {
+                let frames =
+                    detect::detect_frames(image, tile_size, alpha_threshold, min_size, align)?;
+                Self::frames_tile_frames(image_size, tile_size, frames)
+            }
+        }
+    }
+
+    fn grid_tile_frames(
+        image_size: UVec2,
+        tile_size: UVec2,
+        padding: UVec2,
+        margins: URect,
+        order: GridOrder,
+        region: Option<URect>,
+    ) -> Result<TilesetSourceFrames, LayoutError> {
+        let grid_area = match region {
+            Some(region)
+                if region.min.cmple(region.max).all() && region.max.cmple(image_size).all() =>
+            {
+                region.size()
+            }
+            Some(region) => return Err(LayoutError::InvalidRegion { image_size, region }),
+            None => image_size,
+        };
+
+        // Each tile is followed by padding, except for the last in each row and column
+        let adjusted_size = grid_area.saturating_sub(margins.size()) + padding;
+        let stride = tile_size + padding;
+
+        if margins.size().cmpgt(grid_area).any() || adjusted_size % stride != UVec2::ZERO {
+            return Err(LayoutError::InvalidGrid {
+                image_size,
+                tile_size,
+                padding,
+                margins,
+            });
+        }
+
+        let grid_size = adjusted_size / stride;
+        let tile_count =
+            grid_size
+                .element_product()
+                .try_into()
+                .map_err(|_| LayoutError::TooManyTiles {
+                    count: grid_size.element_product() as _,
+                })?;
+
+        Ok(TilesetSourceFrames::Grid {
+            tile_count,
+            tile_size,
+            grid_size,
+            padding,
+            margins,
+            order,
+            region,
+        })
+    }
+
+    fn frames_tile_frames(
+        image_size: UVec2,
+        tile_size: UVec2,
+        frames: Vec<TileFrame>,
+    ) -> Result<TilesetSourceFrames, LayoutError> {
+        let frames = frames
+            .into_iter()
+            .map(|frame| frame.resolve(tile_size))
+            .collect::<Vec<_>>();
+
+        for frame in &frames {
+            if !frame.is_valid(image_size, tile_size) {
+                return Err(LayoutError::InvalidFrame {
+                    tile_size,
+                    image_size,
+                    frame: *frame,
+                });
+            }
+        }
+
+        if frames.len() > usize::from(TileIndex::MAX) {
+            return Err(LayoutError::TooManyTiles {
+                count: frames.len(),
+            });
+        }
+
+        Ok(TilesetSourceFrames::Frames(frames))
+    }
+}
+
+impl TilesetSourceFrames {
+    /// Returns a layout that produces the same frames, such as the grid found by
+    /// [`TilesetLayout::AutoDetect`].
+    pub fn to_layout(&self) -> TilesetLayout {
+        match self {
+            Self::Grid {
+                padding,
+                margins,
+                order,
+                region,
+                ..
+            } => TilesetLayout::Grid {
+                padding: *padding,
+                margins: *margins,
+                order: *order,
+                region: *region,
+            },
+            Self::Frames(frames) => TilesetLayout::Frames(frames.clone()),
+        }
+    }
+
+    pub fn tile_count(&self) -> TileIndex {
+        match self {
+            Self::Grid { tile_count, .. } => *tile_count,
+            Self::Frames(frames) => frames.len() as _,
+        }
+    }
+
+    pub fn get(&self, tile_index: TileIndex) -> Result<TileFrame, LayoutError> {
+        match self {
+            Self::Grid {
+                tile_count,
+                tile_size,
+                grid_size,
+                padding,
+                margins,
+                order,
+                region,
+            } => (tile_index < *tile_count).then(|| {
+                let i = u32::from(tile_index);
+                let grid_index = match order {
+                    GridOrder::RowMajor => UVec2::new(i % grid_size.x, i / grid_size.x),
+                    GridOrder::ColumnMajor => UVec2::new(i / grid_size.y, i % grid_size.y),
+                };
+
+                let origin = region.map_or(UVec2::ZERO, |region| region.min);
+                let min = origin + margins.min + grid_index * (tile_size + padding);
+                let max = min + *tile_size;
+
+                TileFrame {
+                    frame: URect { min, max },
+                    anchor: UVec2::ZERO,
+                    align: None,
+                }
+            }),
+            Self::Frames(frames) => frames.get(usize::from(tile_index)).copied(),
+        }
+        .ok_or_else(|| LayoutError::OutOfRange {
+            idx: tile_index,
+            max: self.tile_count(),
+        })
+    }
+}
@@ -2,10 +2,11 @@ use std::ops::{Deref, Range};
 
 use bevy_app::{App, Plugin};
 use bevy_asset::{Asset, AssetApp, Handle};
-use bevy_image::Image;
+use bevy_image::{Image, TextureAtlasLayout};
 use bevy_platform::collections::HashMap;
 use bevy_reflect::TypePath;
 
+use crate::format::TileTrim;
 pub use crate::importer::TilesetImportSettings;
 
 pub type TileIndex = u16;
@@ -15,6 +16,7 @@ pub mod format;
 pub mod importer;
 pub mod layout;
 pub mod loader;
+mod parallel;
 pub mod process;
 
 #[derive(Default)]
@@ -24,6 +26,7 @@ impl Plugin for TilesetImporterPlugin {
     fn build(&self, app: &mut App) {
         app.init_asset::<Tileset>()
             .init_asset_loader::<loader::TilesetLoader>()
+            .init_asset_loader::<loader::Ktx2TilesetLoader>()
             .init_asset_loader::<process::ImageTilesetLoader>()
             .init_asset_loader::<process::DataTilesetLoader>()
             .register_asset_processor(process::ImageProcess::default())
@@ -37,10 +40,68 @@ impl Plugin for TilesetImporterPlugin {
 
 #[derive(Asset, Clone, TypePath)]
 pub struct Tileset {
+    /// The tileset texture, or its first page if it was split by
+    /// [`TilesetImportSettings::max_array_layers`].
     #[dependency]
     pub texture: Handle<Image>,
+    /// Every page of the tileset texture, starting with `texture`. Pages are labeled
+    /// `texture_<page>`, or the only page is labeled `texture` if the texture was not split.
+    #[dependency]
+    pub pages: Vec<Handle<Image>>,
+    /// The number of tiles in every page except the last.
+    pub page_size: u32,
     pub count: TileIndex,
     pub groups: TileGroups,
+    /// Whether the color channels of the texture are premultiplied by alpha, in which case it
+    /// should be rendered with premultiplied alpha blending.
+    pub premultiplied_alpha: bool,
+    /// Companion texture layers, such as normal maps, with the same tiles as `texture`. Each is
+    /// also labeled `texture_<name>`, or `texture_<name>_<page>` if split into pages.
+    ///
+    /// This holds every page of the first layer, followed by every page of the next, and so on.
+    #[dependency]
+    pub layers: Vec<Handle<Image>>,
+    /// The name of each texture in `layers`.
+    pub layer_names: Vec<String>,
+    /// The rect of each tile, if the tileset was imported with
+    /// [`OutputLayout::Atlas`](format::OutputLayout::Atlas). This is also labeled `atlas_layout`.
+    ///
+    /// Loading an atlas requires the [`TextureAtlasLayout`] asset to be registered, as is done
+    /// by bevy's `ImagePlugin`.
+    #[dependency]
+    pub atlas_layout: Option<Handle<TextureAtlasLayout>>,
+    /// How the frame of each tile was trimmed, indexed by [`TileIndex`]. This is empty unless
+    /// the tileset was imported with [`TilesetImportSettings::trim`].
+    pub tile_trims: Vec<TileTrim>,
+}
+
+impl Tileset {
+    /// Returns the companion texture layer with the given name, if any. If the layer was split
+    /// into pages, this is its first page.
+    pub fn layer(&self, name: &str) -> Option<&Handle<Image>> {
+        self.layer_page(name, 0)
+    }
+
+    /// Returns a page of the companion texture layer with the given name, if any.
+    pub fn layer_page(&self, name: &str, page: usize) -> Option<&Handle<Image>> {
+        let i = self.layer_names.iter().position(|n| n == name)?;
+        if page >= self.pages.len() {
+            return None;
+        }
+        Some(&self.layers[i * self.pages.len() + page])
+    }
+
+    /// Returns how the frame of a tile was trimmed, if the tileset was imported with
+    /// [`TilesetImportSettings::trim`].
+    pub fn tile_trim(&self, index: TileIndex) -> Option<&TileTrim> {
+        self.tile_trims.get(usize::from(index))
+    }
+
+    /// Returns the page containing a tile, along with its array layer within that page.
+    pub fn tile_page(&self, index: TileIndex) -> (usize, u32) {
+        let index = u32::from(index);
+        ((index / self.page_size) as usize, index % self.page_size)
+    }
 }
 
 impl Deref for Tileset {
@@ -1,12 +1,12 @@
 use bevy_asset::{AssetLoader, LoadContext, RenderAssetUsages, io::Reader};
-use bevy_image::ImageSampler;
+use bevy_image::{Image, ImageSampler};
 use bevy_reflect::TypePath;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
 use crate::{
     Tileset,
-    format::{TilesetFile, TilesetFileError},
+    format::{OutputLayout, TilesetFile, TilesetFileError, split_array_pages},
 };
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -81,18 +81,75 @@ impl AssetLoader for TilesetLoader {
         let mut bytes = Vec::new();
         reader.read_to_end(&mut bytes).await?;
 
-        let (count, groups, mut image) =
-            TilesetFile::read(bytes.as_slice())?.into_count_groups_image()?;
-        image.sampler = settings.sampler.clone();
-        image.asset_usage = settings.asset_usage;
+        load_tileset(TilesetFile::read(bytes.as_slice())?, settings, load_context)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        self.file_extension.as_slice()
+    }
+}
 
-        let texture = load_context.add_labeled_asset("texture".into(), image);
+/// Loads a KTX2 2D array texture as a [`Tileset`], with one tile per array layer.
+///
+/// Tile groups are read from the key/value data written by [`TilesetFile::write_ktx2`], so
+/// arrays produced by other tools can be loaded with no groups. See [`TilesetFile::read_ktx2`]
+/// for the supported subset of KTX2.
+///
+/// The default extension is `ts.ktx2`, so that plain `.ktx2` files are still loaded as images by
+/// bevy's own loader. See [`Ktx2TilesetLoader::with_extension`] to use another extension.
+#[derive(TypePath)]
+pub struct Ktx2TilesetLoader {
+    /// The file extension to use for auto-detecting this loader, without the leading dot. May be
+    /// set to `None` to disable extension-based detection.
+    ///
+    /// The default is [`Ktx2TilesetLoader::DEFAULT_EXTENSION`].
+    pub file_extension: Option<&'static str>,
+}
+
+impl Ktx2TilesetLoader {
+    /// The default file extension: a KTX2 **t**ile**s**et.
+    pub const DEFAULT_EXTENSION: &str = "ts.ktx2";
+
+    /// Create a loader using the given file extension.
+    ///
+    /// See [`Ktx2TilesetLoader::file_extension`].
+    pub const fn with_extension(ext: &'static str) -> Self {
+        Self {
+            file_extension: Some(ext),
+        }
+    }
 
-        Ok(Tileset {
-            texture,
-            count,
-            groups,
-        })
+    /// Create a loader with no file extensions.
+    ///
+    /// See [`Ktx2TilesetLoader::file_extension`].
+    pub const fn without_extension() -> Self {
+        Self {
+            file_extension: None,
+        }
+    }
+}
+
+impl Default for Ktx2TilesetLoader {
+    fn default() -> Self {
+        Self::with_extension(Self::DEFAULT_EXTENSION)
+    }
+}
+
+impl AssetLoader for Ktx2TilesetLoader {
+    type Asset = Tileset;
+    type Settings = TilesetLoaderSettings;
+    type Error = TilesetLoaderError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        settings: &Self::Settings,
+        load_context: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+
+        load_tileset(TilesetFile::read_ktx2(&bytes)?, settings, load_context)
     }
 
     fn extensions(&self) -> &[&str] {
@@ -100,10 +157,94 @@ impl AssetLoader for TilesetLoader {
     }
 }
 
+/// Adds the pages of the tileset texture, the pages of each layer texture, and the atlas layout
+/// if any, as labeled assets, and returns the [`Tileset`].
+fn load_tileset(
+    mut file: TilesetFile,
+    settings: &TilesetLoaderSettings,
+    load_context: &mut LoadContext<'_>,
+) -> Result<Tileset, TilesetLoaderError> {
+    let premultiplied_alpha = file.premultiplied_alpha;
+    let tile_trims = std::mem::take(&mut file.tile_trims);
+    let max_array_layers = match file.output_layout {
+        OutputLayout::Array => file.max_array_layers.map(|max| max.max(1)),
+        OutputLayout::Atlas { .. } => None,
+    };
+    let atlas_layout = file
+        .atlas_layout()
+        .map(|layout| load_context.add_labeled_asset("atlas_layout".into(), layout));
+    let layer_images = file.take_layer_images()?;
+    let (count, groups, image) = file.into_count_groups_image()?;
+
+    // Textures that are not split keep a single unnumbered label
+    let mut add_pages = |label: String, image: Image| -> Result<Vec<_>, TilesetFileError> {
+        let pages = match max_array_layers {
+            Some(max_layers) => split_array_pages(image, max_layers)?,
+            None => vec![image],
+        };
+        let numbered = pages.len() > 1;
+
+        Ok(pages
+            .into_iter()
+            .enumerate()
+            .map(|(page, mut image)| {
+                let label = if numbered {
+                    format!("{label}_{page}")
+                } else {
+                    label.clone()
+                };
+                image.sampler = settings.sampler.clone();
+                image.asset_usage = settings.asset_usage;
+                load_context.add_labeled_asset(label, image)
+            })
+            .collect())
+    };
+
+    let pages = add_pages("texture".into(), image)?;
+    let mut layer_names = Vec::new();
+    let mut layers = Vec::new();
+    for (name, image) in layer_images {
+        layers.extend(add_pages(format!("texture_{name}"), image)?);
+        layer_names.push(name);
+    }
+
+    Ok(Tileset {
+        texture: pages[0].clone(),
+        pages,
+        page_size: max_array_layers.unwrap_or(u32::from(count).max(1)),
+        count,
+        groups,
+        premultiplied_alpha,
+        layers,
+        layer_names,
+        atlas_layout,
+        tile_trims,
+    })
+}
+
 #[derive(Debug, Error)]
 pub enum TilesetLoaderError {
     #[error(transparent)]
     Io(#[from] std::io::Error),
+    /// The tileset file was written by an incompatible version and must be re-imported.
+    #[error("stale tileset file, re-import required: {0}")]
+    Stale(#[source] TilesetFileError),
+    /// The tileset file was written by a newer version of this crate, which must be updated to
+    /// load it.
+    #[error("tileset file is newer than this version supports: {0}")]
+    NewerVersion(#[source] TilesetFileError),
     #[error(transparent)]
-    TilesetFile(#[from] TilesetFileError),
+    TilesetFile(TilesetFileError),
+}
+
+impl From<TilesetFileError> for TilesetLoaderError {
+    fn from(err: TilesetFileError) -> Self {
+        if err.is_stale() {
+            Self::Stale(err)
+        } else if err.is_newer_version() {
+            Self::NewerVersion(err)
+        } else {
+            Self::TilesetFile(err)
+        }
+    }
 }
@@ -0,0 +1,53 @@
+use std::{panic, thread};
+
+/// Maps `f` over `items` using scoped threads, preserving the order of the results.
+///
+/// This falls back to mapping on the current thread when parallelism is unavailable, such as on
+/// wasm targets.
+pub(crate) fn par_map<T: Sync, R: Send>(items: &[T], f: impl Fn(&T) -> R + Sync) -> Vec<R> {
+    par_map_init(items, || (), |_, item| f(item))
+}
+
+/// Like [`par_map`], but each thread first creates its own scratch state with `init`, which is
+/// then passed to every call of `f` on that thread.
+pub(crate) fn par_map_init<T: Sync, S, R: Send>(
+    items: &[T],
+    init: impl Fn() -> S + Sync,
+    f: impl Fn(&mut S, &T) -> R + Sync,
+) -> Vec<R> {
+    let threads = thread::available_parallelism()
+        .map_or(1, usize::from)
+        .min(items.len());
+
+    if threads <= 1 {
+        let mut state = init();
+        return items.iter().map(|item| f(&mut state, item)).collect();
+    }
+
+    let chunk_size = items.len().div_ceil(threads);
+    let (init, f) = (&init, &f);
+
+    thread::scope(|scope| {
+        let handles = items
+            .chunks(chunk_size)
+            .map(|chunk| {
+                scope.spawn(move || {
+                    let mut state = init();
+                    chunk
+                        .iter()
+                        .map(|item| f(&mut state, item))
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect::<Vec<_>>();
+
+        handles
+            .into_iter()
+            .flat_map(|handle| {
+                handle
+                    .join()
+                    .unwrap_or_else(|err| panic::resume_unwind(err))
+            })
+            .collect()
+    })
+}
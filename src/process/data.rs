@@ -6,11 +6,15 @@ use bevy_math::{URect, UVec2};
 use bevy_reflect::TypePath;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
+use wgpu_types::TextureFormat;
 
 use crate::{
-    TileSourceIndex, Tileset,
-    importer::{TileFilter, TilesetImportData, TilesetImporter, TilesetSource},
-    layout::{TileFrame, TilesetLayout},
+    Tileset,
+    importer::{
+        TextureKind, TileFilter, TileVariant, TilesetImportData, TilesetImporter, TilesetLayer,
+        TilesetSource,
+    },
+    layout::{FrameAlign, FramePadding, GridOrder, TileFrame, TilesetLayout},
 };
 
 pub type DataProcess = TilesetImporter<DataTilesetLoader>;
@@ -23,8 +27,11 @@ pub struct DataTileset {
     #[serde(default)]
     pub tile_filter: TileFilter,
     #[serde(default)]
-    pub tile_groups: HashMap<String, Vec<TileSourceIndex>>,
+    pub tile_groups: HashMap<String, Vec<TileVariant>>,
     pub sources: Vec<DataTilesetSource>,
+    /// Companion texture layers, such as normal maps, keyed by name.
+    #[serde(default)]
+    pub layers: HashMap<String, DataTilesetLayer>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -32,6 +39,20 @@ pub struct DataTilesetSource {
     pub path: AssetPath<'static>,
     #[serde(default)]
     pub layout: DataSourceLayout,
+    /// How to fill the parts of each tile outside of its frame.
+    #[serde(default)]
+    pub padding: FramePadding,
+}
+
+/// A companion texture layer. See [`TilesetLayer`].
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DataTilesetLayer {
+    /// One image per source, in the same order as [`DataTileset::sources`].
+    pub paths: Vec<AssetPath<'static>>,
+    #[serde(default)]
+    pub texture_kind: TextureKind,
+    #[serde(default)]
+    pub texture_format: Option<TextureFormat>,
 }
 
 #[derive(Debug, Default, Serialize, Deserialize)]
@@ -43,16 +64,50 @@ pub enum DataSourceLayout {
         padding: UVec2,
         #[serde(default)]
         margins: URect,
+        #[serde(default)]
+        order: GridOrder,
+        #[serde(default)]
+        region: Option<URect>,
     },
     Frames(Vec<TileFrame>),
+    /// See [`TilesetLayout::AutoDetect`].
+    AutoDetect,
+    /// See [`TilesetLayout::DetectFrames`].
+    DetectFrames {
+        alpha_threshold: f32,
+        #[serde(default)]
+        min_size: UVec2,
+        #[serde(default)]
+        align: FrameAlign,
+    },
 }
 
 impl DataSourceLayout {
     pub fn into_layout(self) -> TilesetLayout {
         match self {
             Self::Auto => TilesetLayout::unpadded_grid(),
-            Self::Grid { padding, margins } => TilesetLayout::Grid { padding, margins },
+            Self::Grid {
+                padding,
+                margins,
+                order,
+                region,
+            } => TilesetLayout::Grid {
+                padding,
+                margins,
+                order,
+                region,
+            },
             Self::Frames(frames) => TilesetLayout::Frames(frames),
+            Self::AutoDetect => TilesetLayout::AutoDetect,
+            Self::DetectFrames {
+                alpha_threshold,
+                min_size,
+                align,
+            } => TilesetLayout::DetectFrames {
+                alpha_threshold,
+                min_size,
+                align,
+            },
         }
     }
 }
@@ -79,37 +134,53 @@ impl AssetLoader for DataTilesetLoader {
             tile_filter,
             tile_groups,
             sources,
+            layers,
         } = ron::de::from_bytes(&bytes)?;
 
+        // Check every layer before loading any textures
+        if let Some((name, layer)) = layers
+            .iter()
+            .find(|(_, layer)| layer.paths.len() != sources.len())
+        {
+            return Err(DataTilesetError::LayerPaths {
+                layer: name.clone(),
+                expected: sources.len(),
+                found: layer.paths.len(),
+            });
+        }
+
         let mut loaded_sources = Vec::new();
-        for DataTilesetSource { path, layout } in sources {
-            let source_asset = load_context
-                .loader()
-                .immediate()
-                .with_unknown_type()
-                .load(&path)
-                .await?;
-
-            let asset_type_id = source_asset.asset_type_id();
-            let texture = if asset_type_id == TypeId::of::<Image>() {
-                source_asset.take::<Image>().unwrap()
-            } else if asset_type_id == TypeId::of::<Tileset>() {
-                let tileset = source_asset.downcast::<Tileset>().ok().unwrap();
-                tileset
-                    .get_labeled("texture")
-                    .and_then(|erased| erased.get::<Image>())
-                    .ok_or(DataTilesetError::InvalidSourceTexture(path))?
-                    .clone()
-            } else {
-                return Err(DataTilesetError::UnknownSourceType(
-                    path,
-                    source_asset.asset_type_name(),
-                ));
-            };
+        for DataTilesetSource {
+            path,
+            layout,
+            padding,
+        } in sources
+        {
+            let texture = load_texture(load_context, path).await?;
 
             loaded_sources.push(TilesetSource {
                 texture,
                 layout: layout.into_layout(),
+                padding,
+            });
+        }
+
+        // Sort the layers so that they are stored in a consistent order
+        let mut layers = layers.into_iter().collect::<Vec<_>>();
+        layers.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+        let mut loaded_layers = Vec::new();
+        for (name, layer) in layers {
+            let mut textures = Vec::new();
+            for path in layer.paths {
+                textures.push(load_texture(load_context, path).await?);
+            }
+
+            loaded_layers.push(TilesetLayer {
+                name,
+                textures,
+                texture_kind: layer.texture_kind,
+                texture_format: layer.texture_format,
             });
         }
 
@@ -118,6 +189,7 @@ impl AssetLoader for DataTilesetLoader {
             tile_filter,
             tile_groups: tile_groups.into_iter().collect(),
             sources: loaded_sources,
+            layers: loaded_layers,
         })
     }
 
@@ -126,6 +198,36 @@ impl AssetLoader for DataTilesetLoader {
     }
 }
 
+/// Loads an image, or the texture of a tileset, to use as a source texture.
+async fn load_texture(
+    load_context: &mut LoadContext<'_>,
+    path: AssetPath<'static>,
+) -> Result<Image, DataTilesetError> {
+    let source_asset = load_context
+        .loader()
+        .immediate()
+        .with_unknown_type()
+        .load(&path)
+        .await?;
+
+    let asset_type_id = source_asset.asset_type_id();
+    if asset_type_id == TypeId::of::<Image>() {
+        Ok(source_asset.take::<Image>().unwrap())
+    } else if asset_type_id == TypeId::of::<Tileset>() {
+        let tileset = source_asset.downcast::<Tileset>().ok().unwrap();
+        Ok(tileset
+            .get_labeled("texture")
+            .and_then(|erased| erased.get::<Image>())
+            .ok_or(DataTilesetError::InvalidSourceTexture(path))?
+            .clone())
+    } else {
+        Err(DataTilesetError::UnknownSourceType(
+            path,
+            source_asset.asset_type_name(),
+        ))
+    }
+}
+
 #[derive(Debug, Error)]
 pub enum DataTilesetError {
     #[error(transparent)]
@@ -138,4 +240,10 @@ pub enum DataTilesetError {
     UnknownSourceType(AssetPath<'static>, &'static str),
     #[error("unable to get texture from source asset {0:?}")]
     InvalidSourceTexture(AssetPath<'static>),
+    #[error("layer {layer:?} has {found} paths, but the tileset has {expected} sources")]
+    LayerPaths {
+        layer: String,
+        expected: usize,
+        found: usize,
+    },
 }
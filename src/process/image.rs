@@ -11,8 +11,8 @@ use wgpu_types::TextureFormat;
 
 use crate::{
     TileIndex,
-    importer::{TileFilter, TilesetImportData, TilesetImporter, TilesetSource},
-    layout::{TileFrame, TilesetLayout},
+    importer::{TileFilter, TileVariant, TilesetImportData, TilesetImporter, TilesetSource},
+    layout::{FramePadding, GridOrder, TileFrame, TilesetLayout},
 };
 
 pub type ImageProcess = TilesetImporter<ImageTilesetLoader>;
@@ -20,6 +20,9 @@ pub type ImageProcess = TilesetImporter<ImageTilesetLoader>;
 #[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct ImageTilesetSettings {
     pub layout: ImageLayoutSetting,
+    /// How to fill the parts of each tile outside of its frame.
+    #[serde(default)]
+    pub padding: FramePadding,
     pub tile_filter: ImageTileFilter,
     pub tile_groups: HashMap<String, Vec<TileIndex>>,
     pub format: ImageFormatSetting,
@@ -35,6 +38,10 @@ pub enum ImageLayoutSetting {
         tile_size: UVec2,
         padding: UVec2,
         margins: URect,
+        #[serde(default)]
+        order: GridOrder,
+        #[serde(default)]
+        region: Option<URect>,
     },
     Frames {
         tile_size: UVec2,
@@ -50,7 +57,17 @@ impl ImageLayoutSetting {
                 tile_size,
                 padding,
                 margins,
-            } => (TilesetLayout::Grid { padding, margins }, tile_size),
+                order,
+                region,
+            } => (
+                TilesetLayout::Grid {
+                    padding,
+                    margins,
+                    order,
+                    region,
+                },
+                tile_size,
+            ),
             Self::Frames {
                 tile_size,
                 ref frames,
@@ -65,6 +82,10 @@ pub enum ImageTileFilter {
     All,
     None,
     List(Vec<TileIndex>),
+    /// See [`TileFilter::NonTransparent`].
+    NonTransparent {
+        alpha_threshold: f32,
+    },
 }
 
 impl ImageTileFilter {
@@ -77,6 +98,9 @@ impl ImageTileFilter {
                     .map(|tile_index| (source_id, *tile_index))
                     .collect(),
             ),
+            Self::NonTransparent { alpha_threshold } => TileFilter::NonTransparent {
+                alpha_threshold: *alpha_threshold,
+            },
         }
     }
 }
@@ -104,6 +128,7 @@ impl AssetLoader for ImageTilesetLoader {
         reader: &mut dyn Reader,
         &ImageTilesetSettings {
             ref layout,
+            padding,
             ref format,
             ref tile_groups,
             ref tile_filter,
@@ -131,7 +156,10 @@ impl AssetLoader for ImageTilesetLoader {
             .map(|(name, group)| {
                 (
                     name.clone(),
-                    group.iter().map(|tile_index| (0, *tile_index)).collect(),
+                    group
+                        .iter()
+                        .map(|tile_index| TileVariant::from((0, *tile_index)))
+                        .collect(),
                 )
             })
             .collect();
@@ -140,7 +168,12 @@ impl AssetLoader for ImageTilesetLoader {
             tile_size,
             tile_filter,
             tile_groups,
-            sources: vec![TilesetSource { texture, layout }],
+            sources: vec![TilesetSource {
+                texture,
+                layout,
+                padding,
+            }],
+            layers: Vec::new(),
         })
     }
 }
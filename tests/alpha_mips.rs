@@ -0,0 +1,104 @@
+use bevy_asset::RenderAssetUsages;
+use bevy_image::Image;
+use bevy_math::UVec2;
+use bevy_tileset_importer::{
+    TilesetImportSettings,
+    format::TilesetFile,
+    importer::{AlphaMipMode, ImportTilesetError, TileFilter, TilesetImportData, TilesetSource},
+    layout::{FramePadding, TilesetLayout},
+};
+use wgpu_types::{Extent3d, TextureDimension, TextureFormat};
+
+const SIZE: u32 = 32;
+const ALPHA_TEST: f32 = 0.7;
+
+/// Imports a single tile with noisy alpha, like a clump of leaves.
+fn import(alpha_mip_mode: AlphaMipMode) -> Result<TilesetFile, ImportTilesetError> {
+    let data = (0..SIZE)
+        .flat_map(|y| {
+            (0..SIZE).flat_map(move |x| {
+                let alpha = ((x * 37 + y * 91) * 53 % 256) as u8;
+                [40, 160, 40, alpha]
+            })
+        })
+        .collect();
+    let texture = Image::new(
+        Extent3d {
+            width: SIZE,
+            height: SIZE,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8Unorm,
+        RenderAssetUsages::all(),
+    );
+
+    let settings = TilesetImportSettings {
+        generate_mips: true,
+        alpha_mip_mode,
+        ..Default::default()
+    };
+    TilesetImportData {
+        tile_size: UVec2::splat(SIZE),
+        tile_filter: TileFilter::All,
+        tile_groups: Vec::new(),
+        sources: vec![TilesetSource {
+            texture,
+            layout: TilesetLayout::unpadded_grid(),
+            padding: FramePadding::default(),
+        }],
+        layers: Vec::new(),
+    }
+    .import(&settings)
+}
+
+/// Returns the fraction of pixels in each mip level with an alpha above [`ALPHA_TEST`].
+fn coverage_per_level(alpha_mip_mode: AlphaMipMode) -> Vec<f32> {
+    let file = import(alpha_mip_mode).unwrap();
+    let mut levels = Vec::new();
+    let mut pixels = file.texture_data.chunks_exact(4);
+    for m in 0..file.texture_mips {
+        let len = ((SIZE >> m) * (SIZE >> m)) as usize;
+        let covered = pixels
+            .by_ref()
+            .take(len)
+            .filter(|pixel| f32::from(pixel[3]) / 255.0 > ALPHA_TEST)
+            .count();
+        levels.push(covered as f32 / len as f32);
+    }
+    levels
+}
+
+#[test]
+fn premultiplied_loses_coverage() {
+    // Averaging pulls alpha towards the mean, so fewer pixels pass the alpha test
+    let levels = coverage_per_level(AlphaMipMode::Premultiplied);
+    assert!(levels[1] < 0.5 * levels[0]);
+}
+
+#[test]
+fn preserve_coverage() {
+    let levels = coverage_per_level(AlphaMipMode::PreserveCoverage {
+        alpha_test: ALPHA_TEST,
+    });
+    // The smallest levels are too coarse to match closely
+    for (m, coverage) in levels.iter().enumerate().take(5) {
+        assert!(
+            (coverage - levels[0]).abs() < 0.1,
+            "mip {m} coverage was {coverage}, expected {}",
+            levels[0]
+        );
+    }
+}
+
+#[test]
+fn alpha_test_must_be_between_zero_and_one() {
+    for alpha_test in [0.0, -0.5, 1.0, f32::NAN] {
+        let err = import(AlphaMipMode::PreserveCoverage { alpha_test }).unwrap_err();
+        assert!(
+            matches!(err, ImportTilesetError::AlphaTest(_)),
+            "{alpha_test}: {err}"
+        );
+    }
+}
@@ -0,0 +1,97 @@
+use bevy_math::{URect, UVec2};
+use bevy_tileset_importer::{
+    TilesetImportSettings,
+    format::{Compression, OutputLayout, Storage, TilesetFile, TilesetFileError},
+    importer::ImportTilesetError,
+};
+use wgpu_types::TextureFormat;
+
+mod common;
+
+const ATLAS: OutputLayout = OutputLayout::Atlas {
+    columns: 4,
+    padding: 2,
+};
+
+#[test]
+fn atlas_matches_array_tiles() {
+    let settings = TilesetImportSettings {
+        generate_mips: true,
+        output_layout: ATLAS,
+        ..Default::default()
+    };
+    let file = common::letters().import(&settings).unwrap();
+    // Halving a padding of 2 leaves a single odd level, so only two levels are generated
+    assert_eq!(file.texture_mips, 2);
+
+    let mut bytes = Vec::new();
+    file.write(Compression::None, Storage::Contiguous, &mut bytes)
+        .unwrap();
+    let file = TilesetFile::read(bytes.as_slice()).unwrap();
+    assert_eq!(file.output_layout, ATLAS);
+
+    let layout = file.atlas_layout().unwrap();
+    assert_eq!(layout.size, UVec2::new(80, 40));
+    assert_eq!(layout.textures.len(), usize::from(file.tile_count));
+    assert_eq!(
+        layout.textures[5],
+        URect::from_corners(UVec2::new(22, 22), UVec2::new(38, 38))
+    );
+
+    let tile_data = file.texture_data.clone();
+    let tile_len = tile_data.len() / usize::from(file.tile_count);
+    let (_, groups, image) = file.into_count_groups_image().unwrap();
+    assert_eq!(groups.group("vowels"), &[0, 4]);
+    assert_eq!(image.texture_descriptor.size.width, 80);
+    assert_eq!(image.texture_descriptor.size.height, 40);
+    assert_eq!(image.texture_descriptor.mip_level_count, 2);
+
+    let atlas = image.data.unwrap();
+    let pixel = |x: u32, y: u32| {
+        let i = (y as usize * 80 + x as usize) * 4;
+        &atlas[i..i + 4]
+    };
+
+    for (tile, rect) in layout.textures.iter().enumerate() {
+        let tile = &tile_data[tile * tile_len..];
+        for y in 0..16 {
+            for x in 0..16 {
+                let i = (y * 16 + x) as usize * 4;
+                assert_eq!(pixel(rect.min.x + x, rect.min.y + y), &tile[i..i + 4]);
+            }
+        }
+
+        // The gutter repeats the nearest edge pixel
+        assert_eq!(pixel(rect.min.x - 2, rect.min.y - 2), &tile[..4]);
+        assert_eq!(
+            pixel(rect.max.x + 1, rect.min.y + 3),
+            pixel(rect.max.x - 1, rect.min.y + 3)
+        );
+        assert_eq!(
+            pixel(rect.min.x + 5, rect.max.y + 1),
+            pixel(rect.min.x + 5, rect.max.y - 1)
+        );
+    }
+}
+
+#[test]
+fn atlas_rejects_block_compression() {
+    let settings = TilesetImportSettings {
+        texture_format: Some(TextureFormat::Bc7RgbaUnormSrgb),
+        output_layout: ATLAS,
+        ..Default::default()
+    };
+    let err = common::letters().import(&settings).unwrap_err();
+    assert!(matches!(err, ImportTilesetError::AtlasFormat(_)), "{err}");
+}
+
+#[test]
+fn atlas_is_not_written_to_ktx2() {
+    let settings = TilesetImportSettings {
+        output_layout: ATLAS,
+        ..Default::default()
+    };
+    let file = common::letters().import(&settings).unwrap();
+    let err = file.write_ktx2(Vec::new()).unwrap_err();
+    assert!(matches!(err, TilesetFileError::Ktx2Atlas), "{err}");
+}
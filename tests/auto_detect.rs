@@ -0,0 +1,121 @@
+use bevy_image::Image;
+use bevy_math::UVec2;
+use bevy_tileset_importer::{
+    TilesetImportSettings,
+    importer::{ImportTilesetError, SourceError, TileFilter, TilesetImportData, TilesetSource},
+    layout::{FramePadding, LayoutError, TilesetLayout},
+};
+use wgpu_types::Extent3d;
+
+mod common;
+
+/// Copies the 16x16 tiles of `tiles_abcd.png` into a sheet with a 2px border and 1px separator
+/// lines between the tiles, all filled with an opaque color.
+fn separated_sheet() -> Image {
+    let tiles = common::load_image("tiles_abcd.png");
+    let tile_data = tiles.data.as_deref().unwrap();
+    let grid = tiles.size() / 16;
+
+    let size = 2 + grid * 16 + (grid - 1) + 2;
+    let mut sheet = Image::new_fill(
+        Extent3d {
+            width: size.x,
+            height: size.y,
+            depth_or_array_layers: 1,
+        },
+        tiles.texture_descriptor.dimension,
+        &[255, 0, 255, 255],
+        tiles.texture_descriptor.format,
+        tiles.asset_usage,
+    );
+
+    let sheet_data = sheet.data.as_mut().unwrap();
+    for y in 0..tiles.height() {
+        for tile_x in 0..grid.x {
+            let src = ((y * tiles.width() + tile_x * 16) * 4) as usize;
+            let dst = (((2 + y + y / 16) * size.x + 2 + tile_x * 17) * 4) as usize;
+            sheet_data[dst..dst + 64].copy_from_slice(&tile_data[src..src + 64]);
+        }
+    }
+    sheet
+}
+
+fn import(texture: Image, layout: TilesetLayout) -> Result<Vec<u8>, ImportTilesetError> {
+    let data = TilesetImportData {
+        tile_size: UVec2::splat(16),
+        tile_filter: TileFilter::All,
+        tile_groups: Vec::new(),
+        sources: vec![TilesetSource {
+            texture,
+            layout,
+            padding: FramePadding::default(),
+        }],
+        layers: Vec::new(),
+    };
+    let file = data.import(&TilesetImportSettings::default())?;
+    Ok(file.texture_data)
+}
+
+#[test]
+fn detects_separators_and_border() {
+    let expected = import(
+        common::load_image("tiles_abcd.png"),
+        TilesetLayout::unpadded_grid(),
+    )
+    .unwrap();
+    let detected = import(separated_sheet(), TilesetLayout::AutoDetect).unwrap();
+    assert_eq!(detected, expected);
+}
+
+#[test]
+fn detects_unpadded_grid() {
+    let expected = import(
+        common::load_image("tiles_abcd.png"),
+        TilesetLayout::unpadded_grid(),
+    )
+    .unwrap();
+    let detected = import(
+        common::load_image("tiles_abcd.png"),
+        TilesetLayout::AutoDetect,
+    )
+    .unwrap();
+    assert_eq!(detected, expected);
+}
+
+#[test]
+fn reports_missing_grid() {
+    let mut sheet = separated_sheet();
+    // Shifting the sheet by a column leaves a partial tile that no grid can explain
+    let mut image = Image::new_fill(
+        Extent3d {
+            width: sheet.width() + 15,
+            height: sheet.height(),
+            depth_or_array_layers: 1,
+        },
+        sheet.texture_descriptor.dimension,
+        &[255, 255, 255, 255],
+        sheet.texture_descriptor.format,
+        sheet.asset_usage,
+    );
+    let row_bytes = sheet.width() as usize * 4;
+    let sheet_data = sheet.data.take().unwrap();
+    let image_data = image.data.as_mut().unwrap();
+    for (y, row) in sheet_data.chunks_exact(row_bytes).enumerate() {
+        let dst = y * (row_bytes + 60);
+        image_data[dst..dst + row_bytes].copy_from_slice(row);
+        // A stripe of varying color through the extra columns
+        image_data[dst + row_bytes + 4 * 7] = y as u8;
+    }
+
+    let err = import(image, TilesetLayout::AutoDetect).unwrap_err();
+    assert!(
+        matches!(
+            err,
+            ImportTilesetError::ValidateSource(SourceError::SourceLayout {
+                err: LayoutError::DetectGrid { .. },
+                ..
+            })
+        ),
+        "{err}"
+    );
+}
@@ -0,0 +1,175 @@
+//! Decodes block-compressed tilesets and compares them against the same tiles imported
+//! uncompressed.
+
+use bevy_asset::RenderAssetUsages;
+use bevy_image::Image;
+use bevy_math::UVec2;
+use bevy_tileset_importer::{
+    TilesetImportSettings,
+    format::TilesetFile,
+    importer::{TileFilter, TilesetImportData, TilesetSource},
+    layout::{FramePadding, TilesetLayout},
+};
+use texpresso::Format;
+use wgpu_types::{Extent3d, TextureDimension, TextureFormat};
+
+const TILE_SIZE: u32 = 8;
+
+/// Imports two 8x8 tiles of smooth gradients, with mipmaps down to 1x1.
+fn import(texture_format: TextureFormat) -> TilesetFile {
+    let (width, height) = (2 * TILE_SIZE, TILE_SIZE);
+    let data = (0..height)
+        .flat_map(|y| {
+            (0..width).flat_map(move |x| {
+                let (x, y) = (x as u8, y as u8);
+                [100 + x * 6, 80 + y * 6, 200 - x * 3 - y * 3, 255 - x * 3]
+            })
+        })
+        .collect();
+    let texture = Image::new(
+        Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8Unorm,
+        RenderAssetUsages::all(),
+    );
+
+    TilesetImportData {
+        tile_size: UVec2::splat(TILE_SIZE),
+        tile_filter: TileFilter::All,
+        tile_groups: Vec::new(),
+        sources: vec![TilesetSource {
+            texture,
+            layout: TilesetLayout::unpadded_grid(),
+            padding: FramePadding::default(),
+        }],
+        layers: Vec::new(),
+    }
+    .import(&TilesetImportSettings {
+        texture_format: Some(texture_format),
+        generate_mips: true,
+        ..Default::default()
+    })
+    .unwrap()
+}
+
+fn mip_size(level: u32) -> UVec2 {
+    UVec2::splat((TILE_SIZE >> level).max(1))
+}
+
+/// Decodes a single BC7 block, which must use mode 6.
+fn decode_bc7_mode6(block: &[u8]) -> [[u8; 4]; 16] {
+    let bits = u128::from_le_bytes(block.try_into().unwrap());
+    let mut offset = 0;
+    let mut take = |count: u32| {
+        let value = (bits >> offset) as u32 & ((1 << count) - 1);
+        offset += count;
+        value
+    };
+
+    assert_eq!(take(7), 1 << 6, "not a mode 6 block");
+    // Channels are interleaved, with both endpoints of red first
+    let channels: [[u32; 2]; 4] = std::array::from_fn(|_| [take(7), take(7)]);
+    let mut endpoints = [0, 1].map(|e| channels.map(|channel| channel[e]));
+    for endpoint in &mut endpoints {
+        let p_bit = take(1);
+        *endpoint = endpoint.map(|c| (c << 1) | p_bit);
+    }
+
+    const WEIGHTS: [u32; 16] = [0, 4, 9, 13, 17, 21, 26, 30, 34, 38, 43, 47, 51, 55, 60, 64];
+    std::array::from_fn(|i| {
+        let w = WEIGHTS[take(if i == 0 { 3 } else { 4 }) as usize];
+        std::array::from_fn(|c| {
+            (((64 - w) * endpoints[0][c] + w * endpoints[1][c] + 32) >> 6) as u8
+        })
+    })
+}
+
+/// Decodes one mip level of `format` into RGBA8.
+fn decode(format: TextureFormat, data: &[u8], size: UVec2) -> Vec<u8> {
+    let (width, height) = (size.x as usize, size.y as usize);
+    let mut rgba = vec![0; 4 * width * height];
+    match format {
+        TextureFormat::Bc1RgbaUnorm => Format::Bc1.decompress(data, width, height, &mut rgba),
+        TextureFormat::Bc3RgbaUnorm => Format::Bc3.decompress(data, width, height, &mut rgba),
+        TextureFormat::Bc7RgbaUnorm => {
+            let blocks_wide = width.div_ceil(4);
+            for (i, block) in data.chunks_exact(16).enumerate() {
+                let (bx, by) = (4 * (i % blocks_wide), 4 * (i / blocks_wide));
+                for (j, pixel) in decode_bc7_mode6(block).into_iter().enumerate() {
+                    let (x, y) = (bx + j % 4, by + j / 4);
+                    if x < width && y < height {
+                        rgba[4 * (x + y * width)..][..4].copy_from_slice(&pixel);
+                    }
+                }
+            }
+        }
+        _ => unreachable!(),
+    }
+    rgba
+}
+
+/// Compares every mip level of every tile against the uncompressed import, allowing each of the
+/// first `channels` channels to differ by at most `max_error`.
+fn check_format(format: TextureFormat, block_bytes: usize, channels: usize, max_error: u8) {
+    let expected = import(TextureFormat::Rgba8Unorm);
+    let file = import(format);
+    assert_eq!(file.texture_format, format);
+    assert_eq!(file.texture_mips, expected.texture_mips);
+    assert_eq!(file.texture_mips, 4);
+
+    let mip_bytes = |level, bytes_per_unit: usize, block: u32| {
+        let size = mip_size(level);
+        (size.x.div_ceil(block) * size.y.div_ceil(block)) as usize * bytes_per_unit
+    };
+    // Every level takes at least one block, including the 2x2 and 1x1 levels
+    let tile_bytes = (0..file.texture_mips)
+        .map(|level| mip_bytes(level, block_bytes, 4))
+        .sum::<usize>();
+    assert_eq!(tile_bytes, 7 * block_bytes);
+    assert_eq!(
+        file.texture_data.len(),
+        usize::from(file.tile_count) * tile_bytes
+    );
+
+    let (mut compressed, mut uncompressed) = (&file.texture_data[..], &expected.texture_data[..]);
+    for tile in 0..file.tile_count {
+        for level in 0..file.texture_mips {
+            let (data, rest) = compressed.split_at(mip_bytes(level, block_bytes, 4));
+            let (pixels, uncompressed_rest) = uncompressed.split_at(mip_bytes(level, 4, 1));
+            (compressed, uncompressed) = (rest, uncompressed_rest);
+
+            let decoded = decode(format, data, mip_size(level));
+            for (i, (actual, expected)) in decoded.chunks(4).zip(pixels.chunks(4)).enumerate() {
+                let error = (0..channels)
+                    .map(|c| actual[c].abs_diff(expected[c]))
+                    .max()
+                    .unwrap();
+                assert!(
+                    error <= max_error,
+                    "{format:?} tile {tile} level {level} pixel {i} is {actual:?}, expected {expected:?}"
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn bc1() {
+    // BC1 only has 1-bit alpha, which is opaque for all of these pixels
+    check_format(TextureFormat::Bc1RgbaUnorm, 8, 3, 28);
+}
+
+#[test]
+fn bc3() {
+    check_format(TextureFormat::Bc3RgbaUnorm, 16, 4, 28);
+}
+
+#[test]
+fn bc7() {
+    check_format(TextureFormat::Bc7RgbaUnorm, 16, 4, 24);
+}
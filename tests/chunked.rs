@@ -0,0 +1,95 @@
+use std::io::Cursor;
+
+use bevy_tileset_importer::{
+    TilesetImportSettings,
+    format::{ChunkedTilesetReader, Compression, Storage, TilesetFile, TilesetFileError},
+};
+
+mod common;
+
+fn import_letters() -> TilesetFile {
+    let settings = TilesetImportSettings {
+        generate_mips: true,
+        ..Default::default()
+    };
+    common::letters().import(&settings).unwrap()
+}
+
+fn write(file: &TilesetFile, storage: Storage) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    file.write(Compression::Zstd(3), storage, &mut bytes)
+        .unwrap();
+    bytes
+}
+
+#[test]
+fn round_trip_chunked() {
+    let file = import_letters();
+    let bytes = write(&file, Storage::Chunked);
+    assert_eq!(TilesetFile::read(bytes.as_slice()).unwrap(), file);
+}
+
+#[test]
+fn read_tiles_and_mips() {
+    let file = import_letters();
+    let mut reader =
+        ChunkedTilesetReader::new(Cursor::new(write(&file, Storage::Chunked))).unwrap();
+
+    let tile_count = usize::from(file.tile_count);
+    let tile_len = file.texture_data.len() / tile_count;
+    for tile_index in 0..file.tile_count {
+        let start = usize::from(tile_index) * tile_len;
+        assert_eq!(
+            reader.read_tile(tile_index).unwrap(),
+            file.texture_data[start..start + tile_len]
+        );
+    }
+
+    let mut mip_start = 0;
+    for level in 0..file.texture_mips {
+        let mip_len = (file.tile_size[0] >> level).max(1) as usize
+            * (file.tile_size[1] >> level).max(1) as usize
+            * 4;
+        let expected = (0..tile_count)
+            .flat_map(|tile| {
+                let start = tile * tile_len + mip_start;
+                &file.texture_data[start..start + mip_len]
+            })
+            .copied()
+            .collect::<Vec<_>>();
+
+        assert_eq!(reader.read_mip(level).unwrap(), expected);
+        mip_start += mip_len;
+    }
+
+    assert!(matches!(
+        reader.read_tile(file.tile_count),
+        Err(TilesetFileError::TileOutOfRange { .. })
+    ));
+    assert!(matches!(
+        reader.read_mip(file.texture_mips),
+        Err(TilesetFileError::MipOutOfRange { .. })
+    ));
+}
+
+#[test]
+fn contiguous_is_not_chunked() {
+    let bytes = write(&import_letters(), Storage::Contiguous);
+    assert!(matches!(
+        ChunkedTilesetReader::new(Cursor::new(bytes)),
+        Err(TilesetFileError::NotChunked)
+    ));
+}
+
+#[test]
+fn chunked_without_mips_is_invalid() {
+    let mut file = import_letters();
+    file.texture_mips = 0;
+    file.texture_data.clear();
+
+    let bytes = write(&file, Storage::Chunked);
+    assert!(matches!(
+        ChunkedTilesetReader::new(Cursor::new(bytes)),
+        Err(TilesetFileError::InvalidData)
+    ));
+}
@@ -0,0 +1,58 @@
+//! Helpers shared between the integration tests.
+
+// Not every test uses every helper.
+#![allow(dead_code)]
+
+use bevy_asset::RenderAssetUsages;
+use bevy_image::{CompressedImageFormats, Image, ImageSampler, ImageType};
+use bevy_math::UVec2;
+use bevy_tileset_importer::{
+    TileSourceIndex,
+    importer::{TileFilter, TileVariant, TilesetImportData, TilesetSource},
+    layout::{FramePadding, TilesetLayout},
+};
+
+/// Loads a png from the `assets` directory.
+pub fn load_image(name: &str) -> Image {
+    let path = format!("{}/assets/{name}", env!("CARGO_MANIFEST_DIR"));
+    let bytes = std::fs::read(&path).unwrap_or_else(|err| panic!("failed to read {path}: {err}"));
+
+    Image::from_buffer(
+        &bytes,
+        ImageType::Extension("png"),
+        CompressedImageFormats::NONE,
+        true,
+        ImageSampler::Default,
+        RenderAssetUsages::all(),
+    )
+    .unwrap_or_else(|err| panic!("failed to decode {path}: {err}"))
+}
+
+/// The import data described by `assets/minimal.ts.ron`, as used by the `letters` example.
+pub fn letters() -> TilesetImportData {
+    let source = |name| TilesetSource {
+        texture: load_image(name),
+        layout: TilesetLayout::unpadded_grid(),
+        padding: FramePadding::default(),
+    };
+
+    TilesetImportData {
+        tile_size: UVec2::splat(16),
+        tile_filter: TileFilter::All,
+        tile_groups: vec![
+            group("vowels", &[(0, 0), (1, 0)]),
+            group("consonants", &[(0, 1), (0, 2), (0, 3), (2, 0)]),
+        ],
+        sources: vec![
+            source("tiles_abcd.png"),
+            source("tile_e.png"),
+            source("tile_f.png"),
+        ],
+        layers: Vec::new(),
+    }
+}
+
+/// A group of untransformed tiles.
+pub fn group(name: &str, tiles: &[TileSourceIndex]) -> (String, Vec<TileVariant>) {
+    (name.into(), tiles.iter().map(|&tile| tile.into()).collect())
+}
@@ -0,0 +1,48 @@
+use bevy_tileset_importer::{
+    TilesetImportSettings,
+    format::{Compression, Storage, TilesetFile, TilesetFileHeader},
+};
+
+mod common;
+
+fn round_trip(compression: Compression) {
+    let settings = TilesetImportSettings {
+        generate_mips: true,
+        compression,
+        ..Default::default()
+    };
+    let file = common::letters().import(&settings).unwrap();
+
+    let mut bytes = Vec::new();
+    file.write(compression, Storage::Contiguous, &mut bytes)
+        .unwrap();
+
+    let header = TilesetFileHeader::read(bytes.as_slice()).unwrap();
+    assert_eq!(
+        header,
+        TilesetFileHeader::current(compression.scheme(), Storage::Contiguous)
+    );
+
+    let read = TilesetFile::read(bytes.as_slice()).unwrap();
+    assert_eq!(read, file);
+}
+
+#[test]
+fn round_trip_none() {
+    round_trip(Compression::None);
+}
+
+#[test]
+fn round_trip_deflate() {
+    round_trip(Compression::Deflate(6));
+}
+
+#[test]
+fn round_trip_zstd() {
+    round_trip(Compression::Zstd(3));
+}
+
+#[test]
+fn round_trip_lz4() {
+    round_trip(Compression::Lz4);
+}
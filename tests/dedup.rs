@@ -0,0 +1,44 @@
+use bevy_tileset_importer::{
+    TilesetImportSettings,
+    format::TilesetFile,
+    importer::TilesetSource,
+    layout::{FramePadding, TilesetLayout},
+};
+
+mod common;
+
+/// Imports the letters with a second copy of `tile_e.png`, referenced from its own group.
+fn import_with_duplicate(dedup_pixels: bool) -> TilesetFile {
+    let mut data = common::letters();
+    data.sources.push(TilesetSource {
+        texture: common::load_image("tile_e.png"),
+        layout: TilesetLayout::unpadded_grid(),
+        padding: FramePadding::default(),
+    });
+    data.tile_groups.push(common::group("e", &[(3, 0)]));
+
+    let settings = TilesetImportSettings {
+        dedup_pixels,
+        ..Default::default()
+    };
+    data.import(&settings).unwrap()
+}
+
+fn group<'a>(file: &'a TilesetFile, name: &str) -> &'a [u16] {
+    let (_, indices) = file.tile_groups.iter().find(|(n, _)| n == name).unwrap();
+    indices
+}
+
+#[test]
+fn dedup_identical_pixels() {
+    let file = import_with_duplicate(true);
+    assert_eq!(file.tile_count, 6);
+    assert_eq!(group(&file, "e"), &group(&file, "vowels")[1..]);
+}
+
+#[test]
+fn no_dedup_by_default() {
+    let file = import_with_duplicate(false);
+    assert_eq!(file.tile_count, 7);
+    assert_eq!(group(&file, "e"), [6]);
+}
@@ -0,0 +1,93 @@
+use bevy_asset::RenderAssetUsages;
+use bevy_image::Image;
+use bevy_math::{URect, UVec2};
+use bevy_tileset_importer::layout::{FrameAlign, LayoutError, TileFrame, TilesetLayout};
+use wgpu_types::{Extent3d, TextureDimension, TextureFormat};
+
+/// A 12x8 image with three islands, and a single stray pixel.
+fn islands() -> Image {
+    let mut image = Image::new_fill(
+        Extent3d {
+            width: 12,
+            height: 8,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        &[0, 0, 0, 0],
+        TextureFormat::Rgba8Unorm,
+        RenderAssetUsages::all(),
+    );
+
+    let mut fill = |rect: URect| {
+        for y in rect.min.y..rect.max.y {
+            for x in rect.min.x..rect.max.x {
+                let i = (y * 12 + x) as usize * 4;
+                image.data.as_mut().unwrap()[i..i + 4].copy_from_slice(&[255; 4]);
+            }
+        }
+    };
+    fill(URect::new(1, 1, 4, 3));
+    // Taller, and higher than the first island, but in the same row
+    fill(URect::new(6, 0, 8, 4));
+    // A second row, with a diagonally connected pixel
+    fill(URect::new(2, 6, 4, 8));
+    fill(URect::new(4, 5, 5, 6));
+    // Noise
+    fill(URect::new(10, 7, 11, 8));
+
+    image
+}
+
+fn detect(tile_size: u32, align: FrameAlign) -> Result<Vec<TileFrame>, LayoutError> {
+    let layout = TilesetLayout::DetectFrames {
+        alpha_threshold: 0.5,
+        min_size: UVec2::splat(2),
+        align,
+    };
+    let frames = layout.tile_frames(&islands(), UVec2::splat(tile_size))?;
+    (0..frames.tile_count()).map(|i| frames.get(i)).collect()
+}
+
+#[test]
+fn frames_are_ordered_by_row() {
+    let frames = detect(4, FrameAlign::Center).unwrap();
+    let expected = [
+        (URect::new(1, 1, 4, 3), UVec2::new(0, 1)),
+        (URect::new(6, 0, 8, 4), UVec2::new(1, 0)),
+        (URect::new(2, 5, 5, 8), UVec2::new(0, 0)),
+    ];
+
+    assert_eq!(frames.len(), expected.len());
+    for (frame, (rect, anchor)) in frames.iter().zip(expected) {
+        assert_eq!(frame.frame, rect);
+        assert_eq!(frame.anchor, anchor);
+    }
+}
+
+#[test]
+fn frames_can_be_bottom_aligned() {
+    let frames = detect(4, FrameAlign::BottomCenter).unwrap();
+    let anchors = frames.iter().map(|frame| frame.anchor).collect::<Vec<_>>();
+    assert_eq!(
+        anchors,
+        [UVec2::new(0, 2), UVec2::new(1, 0), UVec2::new(0, 1)]
+    );
+}
+
+#[test]
+fn oversized_frames_are_an_error() {
+    let err = detect(3, FrameAlign::Center).unwrap_err();
+    assert!(
+        matches!(
+            err,
+            LayoutError::FrameTooLarge {
+                frame: URect {
+                    min: UVec2 { x: 6, y: 0 },
+                    ..
+                },
+                ..
+            }
+        ),
+        "{err}"
+    );
+}
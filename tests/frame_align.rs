@@ -0,0 +1,48 @@
+use bevy_asset::RenderAssetUsages;
+use bevy_image::Image;
+use bevy_math::{URect, UVec2};
+use bevy_tileset_importer::layout::{FrameAlign, TileFrame, TilesetLayout};
+use wgpu_types::{Extent3d, TextureDimension, TextureFormat};
+
+/// Resolves the anchor of a 3x2 frame in an 8x8 tile.
+fn anchor(align: Option<FrameAlign>) -> UVec2 {
+    let image = Image::new_fill(
+        Extent3d {
+            width: 8,
+            height: 8,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        &[0; 4],
+        TextureFormat::Rgba8Unorm,
+        RenderAssetUsages::all(),
+    );
+    let frame = TileFrame {
+        frame: URect::new(0, 0, 3, 2),
+        anchor: UVec2::ONE,
+        align,
+    };
+
+    let frames = TilesetLayout::Frames(vec![frame])
+        .tile_frames(&image, UVec2::splat(8))
+        .unwrap();
+    frames.get(0).unwrap().anchor
+}
+
+#[test]
+fn alignment_replaces_anchor() {
+    assert_eq!(anchor(None), UVec2::ONE);
+    assert_eq!(anchor(Some(FrameAlign::Center)), UVec2::new(2, 3));
+    assert_eq!(anchor(Some(FrameAlign::BottomCenter)), UVec2::new(2, 6));
+    assert_eq!(anchor(Some(FrameAlign::TopLeft)), UVec2::ZERO);
+    assert_eq!(anchor(Some(FrameAlign::Pivot(1.0, 0.25))), UVec2::new(5, 1));
+    assert_eq!(anchor(Some(FrameAlign::Pivot(-1.0, 2.0))), UVec2::new(0, 6));
+}
+
+#[test]
+fn alignment_is_deserialized() {
+    let frame: TileFrame =
+        ron::from_str("(frame: (min: (0, 0), max: (3, 2)), align: Some(Pivot(0.5, 1.0)))").unwrap();
+    assert_eq!(frame.anchor, UVec2::ZERO);
+    assert_eq!(frame.align, Some(FrameAlign::Pivot(0.5, 1.0)));
+}
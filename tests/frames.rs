@@ -0,0 +1,104 @@
+use bevy_asset::RenderAssetUsages;
+use bevy_image::Image;
+use bevy_math::{URect, UVec2};
+use bevy_tileset_importer::{
+    TilesetImportSettings,
+    format::TilesetFile,
+    importer::{ImportTilesetError, TileFilter, TilesetImportData, TilesetSource},
+    layout::{FramePadding, TileFrame, TilesetLayout},
+};
+use wgpu_types::{Extent3d, TextureDimension, TextureFormat};
+
+/// Imports the given frames of an 8x8 image into 4x4 tiles.
+fn import_frames(frames: Vec<TileFrame>) -> Result<TilesetFile, ImportTilesetError> {
+    // Each pixel is (x, y, 0, 255)
+    let data = (0..8u8)
+        .flat_map(|y| (0..8u8).flat_map(move |x| [x, y, 0, 255]))
+        .collect();
+    let texture = Image::new(
+        Extent3d {
+            width: 8,
+            height: 8,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8Unorm,
+        RenderAssetUsages::all(),
+    );
+
+    TilesetImportData {
+        tile_size: UVec2::splat(4),
+        tile_filter: TileFilter::All,
+        tile_groups: Vec::new(),
+        sources: vec![TilesetSource {
+            texture,
+            layout: TilesetLayout::Frames(frames),
+            padding: FramePadding::default(),
+        }],
+        layers: Vec::new(),
+    }
+    .import(&TilesetImportSettings::default())
+}
+
+#[test]
+fn frames_may_touch_the_image_and_tile_edges() {
+    // The bottom right quarter of the image fills the whole tile
+    let corner = TileFrame {
+        frame: URect::new(4, 4, 8, 8),
+        anchor: UVec2::ZERO,
+        align: None,
+    };
+    // The right half of the bottom row, anchored to the bottom of the tile
+    let edge = TileFrame {
+        frame: URect::new(4, 7, 8, 8),
+        anchor: UVec2::new(0, 3),
+        align: None,
+    };
+    let file = import_frames(vec![corner, edge]).unwrap();
+
+    let pixel = |tile: usize, x: usize, y: usize| {
+        let i = (tile * 16 + y * 4 + x) * 4;
+        &file.texture_data[i..i + 4]
+    };
+    assert_eq!(pixel(0, 0, 0), [4, 4, 0, 255]);
+    assert_eq!(pixel(0, 3, 3), [7, 7, 0, 255]);
+    assert_eq!(pixel(1, 0, 2), [0, 0, 0, 0]);
+    assert_eq!(pixel(1, 3, 3), [7, 7, 0, 255]);
+}
+
+#[test]
+fn frames_must_fit() {
+    let invalid = [
+        // Past the edge of the image
+        TileFrame {
+            frame: URect::new(5, 5, 9, 9),
+            anchor: UVec2::ZERO,
+            align: None,
+        },
+        // Past the edge of the tile once anchored
+        TileFrame {
+            frame: URect::new(0, 0, 4, 4),
+            anchor: UVec2::new(1, 0),
+            align: None,
+        },
+        // Empty
+        TileFrame {
+            frame: URect::new(2, 2, 2, 2),
+            anchor: UVec2::ZERO,
+            align: None,
+        },
+        TileFrame {
+            frame: URect::new(2, 2, 4, 2),
+            anchor: UVec2::ZERO,
+            align: None,
+        },
+    ];
+    for frame in invalid {
+        let err = import_frames(vec![frame]).unwrap_err();
+        assert!(
+            matches!(err, ImportTilesetError::ValidateSource(_)),
+            "{frame:?}: {err}"
+        );
+    }
+}
@@ -0,0 +1,72 @@
+use bevy_asset::RenderAssetUsages;
+use bevy_image::Image;
+use bevy_math::{URect, UVec2};
+use bevy_tileset_importer::layout::{GridOrder, LayoutError, TilesetLayout};
+use wgpu_types::{Extent3d, TextureDimension, TextureFormat};
+
+/// A 12x8 image, which holds a 3x2 grid of 4x4 tiles.
+fn image() -> Image {
+    Image::new_fill(
+        Extent3d {
+            width: 12,
+            height: 8,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        &[0; 4],
+        TextureFormat::Rgba8Unorm,
+        RenderAssetUsages::all(),
+    )
+}
+
+fn grid(order: GridOrder, region: Option<URect>) -> Result<Vec<UVec2>, LayoutError> {
+    let layout = TilesetLayout::Grid {
+        padding: UVec2::ZERO,
+        margins: URect::default(),
+        order,
+        region,
+    };
+    let frames = layout.tile_frames(&image(), UVec2::splat(4))?;
+    (0..frames.tile_count())
+        .map(|i| frames.get(i).map(|frame| frame.frame.min))
+        .collect()
+}
+
+#[test]
+fn row_major_numbers_rows_first() {
+    let mins = grid(GridOrder::RowMajor, None).unwrap();
+    let expected = [(0, 0), (4, 0), (8, 0), (0, 4), (4, 4), (8, 4)].map(UVec2::from);
+    assert_eq!(mins, expected);
+}
+
+#[test]
+fn column_major_numbers_columns_first() {
+    let mins = grid(GridOrder::ColumnMajor, None).unwrap();
+    let expected = [(0, 0), (0, 4), (4, 0), (4, 4), (8, 0), (8, 4)].map(UVec2::from);
+    assert_eq!(mins, expected);
+}
+
+#[test]
+fn regions_are_numbered_independently() {
+    let left = grid(GridOrder::RowMajor, Some(URect::new(0, 0, 8, 8))).unwrap();
+    let right = grid(GridOrder::ColumnMajor, Some(URect::new(8, 0, 12, 8))).unwrap();
+    assert_eq!(left, [(0, 0), (4, 0), (0, 4), (4, 4)].map(UVec2::from));
+    assert_eq!(right, [(8, 0), (8, 4)].map(UVec2::from));
+}
+
+#[test]
+fn regions_must_fit_the_image() {
+    let err = grid(GridOrder::RowMajor, Some(URect::new(4, 0, 16, 8))).unwrap_err();
+    assert!(matches!(err, LayoutError::InvalidRegion { .. }), "{err}");
+
+    let inverted = URect {
+        min: UVec2::new(8, 0),
+        max: UVec2::new(4, 8),
+    };
+    let err = grid(GridOrder::RowMajor, Some(inverted)).unwrap_err();
+    assert!(matches!(err, LayoutError::InvalidRegion { .. }), "{err}");
+
+    // A region that cannot hold a whole number of tiles
+    let err = grid(GridOrder::RowMajor, Some(URect::new(0, 0, 6, 8))).unwrap_err();
+    assert!(matches!(err, LayoutError::InvalidGrid { .. }), "{err}");
+}
@@ -0,0 +1,74 @@
+use bevy_asset::RenderAssetUsages;
+use bevy_image::Image;
+use bevy_math::{URect, UVec2};
+use bevy_tileset_importer::{
+    TilesetImportSettings,
+    format::TilesetFile,
+    importer::{ImportTilesetError, TileFilter, TilesetImportData, TilesetSource},
+    layout::{FramePadding, GridOrder, TilesetLayout},
+};
+use wgpu_types::{Extent3d, TextureDimension, TextureFormat};
+
+/// Imports 3x3 tiles from a grid with 1 pixel of padding between tiles.
+fn import_padded(image_size: u32) -> Result<TilesetFile, ImportTilesetError> {
+    // Each pixel is (x, y, 0, 255)
+    let size = image_size as u8;
+    let data = (0..size)
+        .flat_map(|y| (0..size).flat_map(move |x| [x, y, 0, 255]))
+        .collect();
+    let texture = Image::new(
+        Extent3d {
+            width: image_size,
+            height: image_size,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8Unorm,
+        RenderAssetUsages::all(),
+    );
+
+    TilesetImportData {
+        tile_size: UVec2::splat(3),
+        tile_filter: TileFilter::All,
+        tile_groups: Vec::new(),
+        sources: vec![TilesetSource {
+            texture,
+            layout: TilesetLayout::Grid {
+                padding: UVec2::ONE,
+                margins: URect::default(),
+                order: GridOrder::RowMajor,
+                region: None,
+            },
+            padding: FramePadding::default(),
+        }],
+        layers: Vec::new(),
+    }
+    .import(&TilesetImportSettings::default())
+}
+
+#[test]
+fn padding_follows_all_but_the_last_tile() {
+    // 3 + 1 + 3 pixels on each axis, which is not a multiple of the tile size once the trailing
+    // padding is added
+    let file = import_padded(7).unwrap();
+    assert_eq!(file.tile_count, 4);
+
+    // The first pixel of each tile skips the padding before it
+    let tile_bytes = 3 * 3 * 4;
+    let first_pixels: Vec<_> = file
+        .texture_data
+        .chunks_exact(tile_bytes)
+        .map(|tile| [tile[0], tile[1]])
+        .collect();
+    assert_eq!(first_pixels, [[0, 0], [4, 0], [0, 4], [4, 4]]);
+}
+
+#[test]
+fn padded_grid_must_fit_exactly() {
+    let err = import_padded(8).unwrap_err();
+    assert!(
+        matches!(err, ImportTilesetError::ValidateSource(_)),
+        "{err}"
+    );
+}
@@ -0,0 +1,45 @@
+use bevy_tileset_importer::{
+    TilesetImportSettings,
+    format::{Compression, Storage, TilesetFileHeader, TilesetFileInfo},
+};
+
+mod common;
+
+#[test]
+fn read_info_without_texture_data() {
+    let settings = TilesetImportSettings {
+        generate_mips: true,
+        ..Default::default()
+    };
+    let file = common::letters().import(&settings).unwrap();
+
+    for storage in [Storage::Contiguous, Storage::Chunked] {
+        let mut bytes = Vec::new();
+        file.write(Compression::Deflate(6), storage, &mut bytes)
+            .unwrap();
+
+        let info = TilesetFileInfo::read(bytes.as_slice()).unwrap();
+        assert_eq!(
+            info.header,
+            TilesetFileHeader::current(Compression::Deflate(6).scheme(), storage)
+        );
+        assert_eq!(info.tile_size, file.tile_size);
+        assert_eq!(info.tile_count, file.tile_count);
+        assert_eq!(info.texture_format, file.texture_format);
+        assert_eq!(info.texture_mips, file.texture_mips);
+        assert_eq!(info.max_array_layers, None);
+        assert_eq!(info.uncompressed_size, file.texture_data.len() as u64);
+
+        let mut groups = info.tile_groups.clone();
+        groups.sort();
+        assert_eq!(
+            groups,
+            [("consonants".to_string(), 4), ("vowels".to_string(), 2)]
+        );
+
+        // The texture data is not needed, so the info can be read from a truncated file.
+        let compressed_size = info.compressed_size.unwrap() as usize;
+        let truncated = &bytes[..bytes.len() - compressed_size];
+        assert_eq!(TilesetFileInfo::read(truncated).unwrap(), info);
+    }
+}
@@ -0,0 +1,91 @@
+use bevy_tileset_importer::{
+    TilesetImportSettings,
+    format::{KTX2_MAX_ARRAY_LAYERS_KEY, KTX2_TILE_GROUPS_KEY, TilesetFile, TilesetFileError},
+};
+use wgpu_types::TextureFormat;
+
+mod common;
+
+fn import_letters(texture_format: TextureFormat) -> TilesetFile {
+    let settings = TilesetImportSettings {
+        texture_format: Some(texture_format),
+        generate_mips: true,
+        ..Default::default()
+    };
+    common::letters().import(&settings).unwrap()
+}
+
+fn write_ktx2(file: &TilesetFile) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    file.write_ktx2(&mut bytes).unwrap();
+    bytes
+}
+
+#[test]
+fn round_trip_ktx2() {
+    for texture_format in [
+        TextureFormat::Rgba8UnormSrgb,
+        TextureFormat::Bc7RgbaUnormSrgb,
+    ] {
+        let file = import_letters(texture_format);
+        let bytes = write_ktx2(&file);
+        assert_eq!(TilesetFile::read_ktx2(&bytes).unwrap(), file);
+    }
+}
+
+#[test]
+fn ktx2_is_an_array_texture() {
+    let file = import_letters(TextureFormat::Rgba8UnormSrgb);
+    let bytes = write_ktx2(&file);
+
+    let reader = ktx2::Reader::new(bytes.as_slice()).unwrap();
+    let header = reader.header();
+    assert_eq!(header.format, Some(ktx2::Format::R8G8B8A8_SRGB));
+    assert_eq!([header.pixel_width, header.pixel_height], file.tile_size);
+    assert_eq!(header.layer_count, u32::from(file.tile_count));
+    assert_eq!(header.level_count, file.texture_mips);
+
+    let groups = reader
+        .key_value_data()
+        .find(|(key, _)| *key == KTX2_TILE_GROUPS_KEY)
+        .map(|(_, value)| value);
+    assert!(groups.is_some_and(|value| value.ends_with(&[0])));
+}
+
+#[test]
+fn ktx2_keeps_max_array_layers() {
+    let mut file = import_letters(TextureFormat::Rgba8UnormSrgb);
+    file.max_array_layers = Some(4);
+    let bytes = write_ktx2(&file);
+    assert_eq!(TilesetFile::read_ktx2(&bytes).unwrap(), file);
+
+    let reader = ktx2::Reader::new(bytes.as_slice()).unwrap();
+    let value = reader
+        .key_value_data()
+        .find(|(key, _)| *key == KTX2_MAX_ARRAY_LAYERS_KEY)
+        .map(|(_, value)| value);
+    assert_eq!(value, Some(b"4\0".as_slice()));
+}
+
+#[test]
+fn ktx2_without_groups() {
+    let mut file = import_letters(TextureFormat::Rgba8UnormSrgb);
+    file.tile_groups.clear();
+
+    // Strip the key/value data, as if the file had been written by another tool.
+    let mut bytes = write_ktx2(&file);
+    bytes[60..64].copy_from_slice(&0u32.to_le_bytes());
+    assert_eq!(TilesetFile::read_ktx2(&bytes).unwrap(), file);
+}
+
+#[test]
+fn ktx2_unsupported_format() {
+    let mut file = import_letters(TextureFormat::Rgba8UnormSrgb);
+    file.texture_format = TextureFormat::Rgba8Uint;
+    assert!(matches!(
+        file.write_ktx2(Vec::new()),
+        Err(TilesetFileError::UnsupportedKtx2Format(
+            TextureFormat::Rgba8Uint
+        ))
+    ));
+}
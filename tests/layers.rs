@@ -0,0 +1,165 @@
+use bevy_tileset_importer::{
+    TilesetImportSettings,
+    format::{Compression, Storage, TilesetFile, TilesetFileError, TilesetFileInfo},
+    importer::{ImportTilesetError, TextureKind, TilesetImportData, TilesetLayer, TilesetSource},
+    layout::{FramePadding, TilesetLayout},
+};
+
+mod common;
+
+/// The letters with a second copy of `tile_e.png`, whose layer texture is `tile_f.png` instead.
+fn letters_with_layer() -> TilesetImportData {
+    let mut data = common::letters();
+    data.sources.push(TilesetSource {
+        texture: common::load_image("tile_e.png"),
+        layout: TilesetLayout::unpadded_grid(),
+        padding: FramePadding::default(),
+    });
+    data.layers.push(TilesetLayer {
+        name: "emissive".into(),
+        textures: ["tiles_abcd.png", "tile_e.png", "tile_f.png", "tile_f.png"]
+            .into_iter()
+            .map(common::load_image)
+            .collect(),
+        texture_kind: TextureKind::Color,
+        texture_format: None,
+    });
+    data
+}
+
+#[test]
+fn layers_share_tile_indices() {
+    let settings = TilesetImportSettings {
+        generate_mips: true,
+        ..Default::default()
+    };
+    let file = letters_with_layer().import(&settings).unwrap();
+    assert_eq!(file.tile_count, 7);
+    assert_eq!(file.layers.len(), 1);
+
+    let layer = &file.layers[0];
+    assert_eq!(layer.name, "emissive");
+    assert_eq!(layer.texture_format, file.texture_format);
+
+    // The first six tiles use the same images in both textures
+    let tile_len = file.texture_data.len() / 7;
+    assert_eq!(layer.texture_data.len(), file.texture_data.len());
+    assert_eq!(
+        layer.texture_data[..6 * tile_len],
+        file.texture_data[..6 * tile_len]
+    );
+    assert_eq!(
+        layer.texture_data[6 * tile_len..],
+        file.texture_data[5 * tile_len..6 * tile_len]
+    );
+}
+
+#[test]
+fn dedup_compares_every_layer() {
+    let settings = TilesetImportSettings {
+        dedup_pixels: true,
+        ..Default::default()
+    };
+    let file = letters_with_layer().import(&settings).unwrap();
+
+    // The duplicate `e` tile has a different layer texture, so it is kept
+    assert_eq!(file.tile_count, 7);
+}
+
+#[test]
+fn layer_missing_tile() {
+    // The first source holds four tiles, but its layer texture only one
+    let mut data = letters_with_layer();
+    data.layers[0].textures[0] = common::load_image("tile_e.png");
+
+    let err = data.import(&TilesetImportSettings::default()).unwrap_err();
+    let ImportTilesetError::InLayer { layer, err } = err else {
+        panic!("unexpected error: {err}");
+    };
+    assert_eq!(layer, "emissive");
+    assert!(matches!(
+        *err,
+        ImportTilesetError::MissingLayerTile {
+            tile_source: (0, 1)
+        }
+    ));
+}
+
+#[test]
+fn layer_texture_count() {
+    let mut data = letters_with_layer();
+    data.layers[0].textures.truncate(2);
+
+    let err = data.import(&TilesetImportSettings::default()).unwrap_err();
+    let ImportTilesetError::LayerTextureCount {
+        layer,
+        expected,
+        found,
+    } = err
+    else {
+        panic!("unexpected error: {err}");
+    };
+    assert_eq!(layer, "emissive");
+    assert_eq!((expected, found), (4, 2));
+}
+
+#[test]
+fn layer_names_are_not_page_labels() {
+    for name in ["", "1"] {
+        let mut data = letters_with_layer();
+        data.layers[0].name = name.into();
+        assert!(matches!(
+            data.import(&TilesetImportSettings::default()),
+            Err(ImportTilesetError::LayerName(n)) if n == name
+        ));
+    }
+}
+
+#[test]
+fn layers_round_trip() {
+    let file = letters_with_layer()
+        .import(&TilesetImportSettings::default())
+        .unwrap();
+
+    for storage in [Storage::Contiguous, Storage::Chunked] {
+        let mut bytes = Vec::new();
+        file.write(Compression::Deflate(1), storage, &mut bytes)
+            .unwrap();
+        assert_eq!(TilesetFile::read(bytes.as_slice()).unwrap(), file);
+
+        let info = TilesetFileInfo::read(bytes.as_slice()).unwrap();
+        let [layer] = info.layers.as_slice() else {
+            panic!("expected one layer: {:?}", info.layers);
+        };
+        assert_eq!(layer.name, "emissive");
+        assert_eq!(layer.texture_format, file.texture_format);
+        assert_eq!(
+            layer.uncompressed_size,
+            file.layers[0].texture_data.len() as u64
+        );
+
+        // The layer data is the last thing in the file, after its length prefix
+        let layer_start = bytes.len() - layer.compressed_size as usize;
+        assert_eq!(
+            bytes[layer_start - 8..layer_start],
+            layer.compressed_size.to_le_bytes()
+        );
+    }
+
+    assert!(matches!(
+        file.write_ktx2(&mut Vec::new()),
+        Err(TilesetFileError::Ktx2Layers)
+    ));
+}
+
+#[test]
+fn file_encodes_with_bincode() {
+    let file = letters_with_layer()
+        .import(&TilesetImportSettings::default())
+        .unwrap();
+
+    let config = bincode::config::standard();
+    let bytes = bincode::encode_to_vec(&file, config).unwrap();
+    let (decoded, _): (TilesetFile, _) = bincode::decode_from_slice(&bytes, config).unwrap();
+    assert_eq!(decoded, file);
+}
@@ -0,0 +1,88 @@
+use std::path::PathBuf;
+
+use bevy_app::{App, TaskPoolPlugin};
+use bevy_asset::{AssetApp, AssetPlugin, AssetServer, Assets, LoadState};
+use bevy_image::Image;
+use bevy_tileset_importer::{
+    Tileset, TilesetImportSettings, TilesetImporterPlugin,
+    format::{Compression, Storage},
+};
+
+mod common;
+
+/// Imports the letters with `max_array_layers`, then writes them to a new asset directory and
+/// loads them.
+fn load_letters(name: &str, max_array_layers: u32) -> (App, Tileset) {
+    let dir = std::env::temp_dir().join(format!("bevy_tileset_importer_{name}"));
+    std::fs::create_dir_all(&dir).unwrap();
+    let settings = TilesetImportSettings {
+        max_array_layers: Some(max_array_layers),
+        ..Default::default()
+    };
+    let file = common::letters().import(&settings).unwrap();
+    let mut bytes = Vec::new();
+    file.write(Compression::None, Storage::Contiguous, &mut bytes)
+        .unwrap();
+    std::fs::write(dir.join("letters.bts"), bytes).unwrap();
+
+    let mut app = App::new();
+    app.add_plugins((
+        TaskPoolPlugin::default(),
+        AssetPlugin {
+            file_path: path_string(dir),
+            ..Default::default()
+        },
+        TilesetImporterPlugin,
+    ))
+    .init_asset::<Image>();
+
+    let handle = app
+        .world()
+        .resource::<AssetServer>()
+        .load::<Tileset>("letters.bts");
+    for _ in 0..1000 {
+        app.update();
+        match app.world().resource::<AssetServer>().load_state(&handle) {
+            LoadState::Loaded => break,
+            LoadState::Failed(err) => panic!("{err}"),
+            _ => std::thread::sleep(std::time::Duration::from_millis(1)),
+        }
+    }
+
+    let tileset = app
+        .world()
+        .resource::<Assets<Tileset>>()
+        .get(&handle)
+        .expect("tileset should be loaded")
+        .clone();
+    (app, tileset)
+}
+
+fn path_string(path: PathBuf) -> String {
+    path.into_os_string().into_string().unwrap()
+}
+
+fn labels(tileset: &Tileset) -> Vec<String> {
+    tileset
+        .pages
+        .iter()
+        .map(|page| page.path().unwrap().label().unwrap().to_string())
+        .collect()
+}
+
+#[test]
+fn unsplit_texture_keeps_its_label() {
+    let (app, tileset) = load_letters("unsplit", 16);
+    assert_eq!(labels(&tileset), ["texture"]);
+    assert_eq!(tileset.texture, tileset.pages[0]);
+
+    let server = app.world().resource::<AssetServer>();
+    let texture = server.get_handle::<Image>("letters.bts#texture");
+    assert_eq!(texture.as_ref(), Some(&tileset.texture));
+}
+
+#[test]
+fn split_texture_numbers_its_pages() {
+    let (_, tileset) = load_letters("split", 4);
+    assert_eq!(labels(&tileset), ["texture_0", "texture_1"]);
+}
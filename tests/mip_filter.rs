@@ -0,0 +1,173 @@
+//! Compares the mipmaps generated by each filter against the golden images in `tests/golden`.
+//!
+//! Run with `UPDATE_GOLDEN=1` to regenerate the golden images after an intentional change.
+
+use std::path::PathBuf;
+
+use bevy_asset::RenderAssetUsages;
+use bevy_image::Image;
+use bevy_math::UVec2;
+use bevy_tileset_importer::{
+    TilesetImportSettings,
+    importer::{MipFilter, TileFilter, TilesetImportData, TilesetSource},
+    layout::{FramePadding, TilesetLayout},
+};
+use image::RgbaImage;
+use wgpu_types::{Extent3d, TextureDimension, TextureFormat};
+
+mod common;
+
+/// Imports the letters with the given filter, and lays out every mip level of each tile side by
+/// side, with one row per tile.
+fn mip_chains(mip_filter: MipFilter) -> RgbaImage {
+    let settings = TilesetImportSettings {
+        generate_mips: true,
+        mip_filter,
+        ..Default::default()
+    };
+    let file = common::letters().import(&settings).unwrap();
+
+    let [tile_width, tile_height] = file.tile_size;
+    let mip_sizes = (0..file.texture_mips)
+        .map(|m| [(tile_width >> m).max(1), (tile_height >> m).max(1)])
+        .collect::<Vec<_>>();
+    let width = mip_sizes.iter().map(|[w, _]| w).sum();
+    let tile_len = file.texture_data.len() / usize::from(file.tile_count);
+
+    let mut chains = RgbaImage::new(width, tile_height * u32::from(file.tile_count));
+    for (tile, data) in file.texture_data.chunks_exact(tile_len).enumerate() {
+        let mut pixels = data.chunks_exact(4);
+        let mut x0 = 0;
+        for &[w, h] in &mip_sizes {
+            for y in 0..h {
+                for x in 0..w {
+                    let pixel = pixels.next().unwrap().try_into().unwrap();
+                    chains.put_pixel(x0 + x, tile as u32 * tile_height + y, image::Rgba(pixel));
+                }
+            }
+            x0 += w;
+        }
+    }
+    chains
+}
+
+fn check_golden(mip_filter: MipFilter) {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/golden")
+        .join(format!("mips_{mip_filter:?}.png").to_lowercase());
+    let actual = mip_chains(mip_filter);
+
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        actual.save(&path).unwrap();
+        return;
+    }
+
+    let expected = image::open(&path)
+        .unwrap_or_else(|err| panic!("failed to open {}: {err}", path.display()))
+        .into_rgba8();
+    assert_eq!(actual.dimensions(), expected.dimensions());
+
+    // Allow for small differences in floating point results between platforms
+    for (x, y, pixel) in actual.enumerate_pixels() {
+        let expected = expected.get_pixel(x, y);
+        let matches = pixel
+            .0
+            .iter()
+            .zip(expected.0)
+            .all(|(a, b)| a.abs_diff(b) <= 1);
+        assert!(
+            matches,
+            "{mip_filter:?} pixel ({x}, {y}) is {pixel:?}, expected {expected:?}"
+        );
+    }
+}
+
+#[test]
+fn golden_box() {
+    check_golden(MipFilter::Box);
+}
+
+#[test]
+fn golden_bilinear() {
+    check_golden(MipFilter::Bilinear);
+}
+
+#[test]
+fn golden_lanczos3() {
+    check_golden(MipFilter::Lanczos3);
+}
+
+#[test]
+fn golden_kaiser() {
+    check_golden(MipFilter::Kaiser);
+}
+
+#[test]
+fn golden_nearest() {
+    check_golden(MipFilter::Nearest);
+}
+
+/// Generates mips for a single tile of a uniform color, which every level should preserve.
+fn check_uniform_mips(tile_size: UVec2) {
+    let color = [40, 120, 200, 255];
+    let data = || TilesetImportData {
+        tile_size,
+        tile_filter: TileFilter::All,
+        tile_groups: Vec::new(),
+        sources: vec![TilesetSource {
+            texture: Image::new_fill(
+                Extent3d {
+                    width: tile_size.x,
+                    height: tile_size.y,
+                    depth_or_array_layers: 1,
+                },
+                TextureDimension::D2,
+                &color,
+                TextureFormat::Rgba8Unorm,
+                RenderAssetUsages::all(),
+            ),
+            layout: TilesetLayout::unpadded_grid(),
+            padding: FramePadding::default(),
+        }],
+        layers: Vec::new(),
+    };
+
+    for mip_filter in [
+        MipFilter::Box,
+        MipFilter::Bilinear,
+        MipFilter::Lanczos3,
+        MipFilter::Kaiser,
+        MipFilter::Nearest,
+    ] {
+        let settings = TilesetImportSettings {
+            generate_mips: true,
+            mip_filter,
+            ..Default::default()
+        };
+        let file = data().import(&settings).unwrap();
+        assert!(file.texture_mips > 1, "{mip_filter:?}");
+
+        let pixels = (0..file.texture_mips)
+            .map(|m| ((tile_size.x >> m).max(1) * (tile_size.y >> m).max(1)) as usize)
+            .sum::<usize>();
+        assert_eq!(file.texture_data.len(), pixels * 4, "{mip_filter:?}");
+        // Each level is quantized before the next is generated, so allow some drift
+        for pixel in file.texture_data.chunks_exact(4) {
+            let matches = pixel.iter().zip(color).all(|(&a, b)| a.abs_diff(b) <= 3);
+            assert!(
+                matches,
+                "{mip_filter:?} pixel is {pixel:?}, expected {color:?}"
+            );
+        }
+    }
+}
+
+#[test]
+fn non_square_tiles() {
+    check_uniform_mips(UVec2::new(16, 8));
+}
+
+#[test]
+fn odd_sized_tiles() {
+    check_uniform_mips(UVec2::new(5, 3));
+}
@@ -0,0 +1,90 @@
+use bevy_asset::RenderAssetUsages;
+use bevy_image::Image;
+use bevy_math::UVec2;
+use bevy_tileset_importer::{
+    TilesetImportSettings,
+    format::TilesetFile,
+    importer::{ImportTilesetError, TextureKind, TileFilter, TilesetImportData, TilesetSource},
+    layout::{FramePadding, TilesetLayout},
+};
+use wgpu_types::{Extent3d, TextureDimension, TextureFormat};
+
+/// Imports a 2x2 normal map, loaded as sRGB, with normals tilted 37° left and right in a
+/// checkerboard.
+fn import_tilted(
+    texture_kind: TextureKind,
+    texture_format: Option<TextureFormat>,
+) -> Result<TilesetFile, ImportTilesetError> {
+    let (left, right) = ([51, 128, 230, 255], [204, 128, 230, 255]);
+    let texture = Image::new(
+        Extent3d {
+            width: 2,
+            height: 2,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        [left, right, right, left].concat(),
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::all(),
+    );
+
+    let settings = TilesetImportSettings {
+        texture_format,
+        texture_kind,
+        generate_mips: true,
+        ..Default::default()
+    };
+    TilesetImportData {
+        tile_size: UVec2::splat(2),
+        tile_filter: TileFilter::All,
+        tile_groups: Vec::new(),
+        sources: vec![TilesetSource {
+            texture,
+            layout: TilesetLayout::unpadded_grid(),
+            padding: FramePadding::default(),
+        }],
+        layers: Vec::new(),
+    }
+    .import(&settings)
+}
+
+#[test]
+fn normal_mips_are_renormalized() {
+    let file = import_tilted(TextureKind::Normal { two_channel: false }, None).unwrap();
+    assert_eq!(file.texture_format, TextureFormat::Rgba8Unorm);
+    assert_eq!(file.texture_data[..4], [51, 128, 230, 255]);
+
+    // The average normal points straight out, rather than being shortened to a Z of 0.8 (230)
+    let mip = &file.texture_data[16..];
+    assert_eq!(mip[0], 127);
+    assert!(mip[2] >= 254, "mip Z was {}", mip[2]);
+}
+
+#[test]
+fn two_channel_normals() {
+    let file = import_tilted(TextureKind::Normal { two_channel: true }, None).unwrap();
+    assert_eq!(file.texture_format, TextureFormat::Rg8Unorm);
+    assert_eq!(file.texture_data.len(), 2 * 2 * 2 + 2);
+    assert_eq!(file.texture_data[..4], [51, 128, 204, 128]);
+    assert_eq!(file.texture_data[8], 127);
+}
+
+#[test]
+fn two_channel_normals_require_two_channel_format() {
+    let result = import_tilted(
+        TextureKind::Normal { two_channel: true },
+        Some(TextureFormat::Rgba8Unorm),
+    );
+    assert!(matches!(
+        result,
+        Err(ImportTilesetError::TwoChannelFormat(
+            TextureFormat::Rgba8Unorm
+        ))
+    ));
+}
+
+#[test]
+fn linear_data_is_not_srgb() {
+    let file = import_tilted(TextureKind::Linear, Some(TextureFormat::Rgba8UnormSrgb)).unwrap();
+    assert_eq!(file.texture_format, TextureFormat::Rgba8Unorm);
+}
@@ -0,0 +1,75 @@
+use bevy_asset::RenderAssetUsages;
+use bevy_image::Image;
+use bevy_math::UVec2;
+use bevy_tileset_importer::{
+    TilesetImportSettings,
+    importer::{TileFilter, TilesetImportData, TilesetSource},
+    layout::{FramePadding, TilesetLayout},
+};
+use wgpu_types::{Extent3d, TextureDimension, TextureFormat};
+
+/// Imports a 2x2 checkerboard of the packed pixels `a` and `b`, and returns its 1x1 mip.
+fn checkerboard_mip(format: TextureFormat, a: u32, b: u32) -> u32 {
+    let data = [a, b, b, a]
+        .into_iter()
+        .flat_map(u32::to_le_bytes)
+        .collect();
+    let texture = Image::new(
+        Extent3d {
+            width: 2,
+            height: 2,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        data,
+        format,
+        RenderAssetUsages::all(),
+    );
+
+    let settings = TilesetImportSettings {
+        generate_mips: true,
+        ..Default::default()
+    };
+    let file = TilesetImportData {
+        tile_size: UVec2::splat(2),
+        tile_filter: TileFilter::All,
+        tile_groups: Vec::new(),
+        sources: vec![TilesetSource {
+            texture,
+            layout: TilesetLayout::unpadded_grid(),
+            padding: FramePadding::default(),
+        }],
+        layers: Vec::new(),
+    }
+    .import(&settings)
+    .unwrap();
+
+    assert_eq!(file.texture_mips, 2);
+    assert_eq!(file.texture_data[..16], file_pixels(&[a, b, b, a]));
+    u32::from_le_bytes(file.texture_data[16..].try_into().unwrap())
+}
+
+fn file_pixels(pixels: &[u32]) -> Vec<u8> {
+    pixels.iter().copied().flat_map(u32::to_le_bytes).collect()
+}
+
+#[test]
+fn rgb10a2_unorm_mips() {
+    let alpha = 3 << 30;
+    let mip = checkerboard_mip(TextureFormat::Rgb10a2Unorm, alpha, 1020 | alpha);
+    assert_eq!(mip, 510 | alpha);
+}
+
+#[test]
+fn rg11b10_ufloat_mips() {
+    // Red values of 2.0 and 4.0, which average to 3.0
+    let mip = checkerboard_mip(TextureFormat::Rg11b10Ufloat, 16 << 6, 17 << 6);
+    assert_eq!(mip, 16 << 6 | 32);
+}
+
+#[test]
+fn rgb9e5_ufloat_mips() {
+    // Red values of 1.0 and 3.0, which average to 2.0
+    let mip = checkerboard_mip(TextureFormat::Rgb9e5Ufloat, 256 | 16 << 27, 384 | 17 << 27);
+    assert_eq!(mip, 256 | 17 << 27);
+}
@@ -0,0 +1,105 @@
+use bevy_asset::RenderAssetUsages;
+use bevy_color::{Color, palettes::basic::RED};
+use bevy_image::Image;
+use bevy_math::{URect, UVec2};
+use bevy_tileset_importer::{
+    TilesetImportSettings,
+    format::TilesetFile,
+    importer::{ImportTilesetError, TileFilter, TilesetImportData, TilesetSource},
+    layout::{FramePadding, TileFrame, TilesetLayout},
+};
+use wgpu_types::{Extent3d, TextureDimension, TextureFormat};
+
+/// Imports frames from an 8x8 image into 4x4 tiles.
+fn import_frames(
+    frames: Vec<TileFrame>,
+    padding: FramePadding,
+) -> Result<TilesetFile, ImportTilesetError> {
+    // Each pixel is (x, y, 0, 255)
+    let data = (0..8u8)
+        .flat_map(|y| (0..8u8).flat_map(move |x| [x, y, 0, 255]))
+        .collect();
+    let texture = Image::new(
+        Extent3d {
+            width: 8,
+            height: 8,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8Unorm,
+        RenderAssetUsages::all(),
+    );
+
+    TilesetImportData {
+        tile_size: UVec2::splat(4),
+        tile_filter: TileFilter::All,
+        tile_groups: Vec::new(),
+        sources: vec![TilesetSource {
+            texture,
+            layout: TilesetLayout::Frames(frames),
+            padding,
+        }],
+        layers: Vec::new(),
+    }
+    .import(&TilesetImportSettings::default())
+}
+
+/// Imports a 3x3 frame, followed by a 2x2 frame anchored at (1, 1).
+fn import_framed(padding: FramePadding) -> TilesetFile {
+    let first = TileFrame::from_tile_size(UVec2::splat(3));
+    let framed = TileFrame {
+        frame: URect::new(5, 5, 7, 7),
+        anchor: UVec2::ONE,
+        align: None,
+    };
+    import_frames(vec![first, framed], padding).unwrap()
+}
+
+/// Returns the pixels of the second tile.
+fn framed_pixels(file: &TilesetFile) -> Vec<[u8; 4]> {
+    file.texture_data[4 * 4 * 4..]
+        .chunks_exact(4)
+        .map(|p| p.try_into().unwrap())
+        .collect()
+}
+
+#[test]
+fn transparent_padding() {
+    let pixels = framed_pixels(&import_framed(FramePadding::Transparent));
+    assert_eq!(pixels[0], [0; 4]);
+    assert_eq!(pixels[5], [5, 5, 0, 255]);
+    assert_eq!(pixels[15], [0; 4]);
+}
+
+#[test]
+fn extrude_padding() {
+    let pixels = framed_pixels(&import_framed(FramePadding::Extrude));
+    for y in 0..4 {
+        for x in 0..4 {
+            let expected = [x.clamp(1, 2) + 4, y.clamp(1, 2) + 4, 0, 255];
+            assert_eq!(pixels[usize::from(y * 4 + x)], expected, "pixel ({x}, {y})");
+        }
+    }
+}
+
+#[test]
+fn solid_padding() {
+    let pixels = framed_pixels(&import_framed(FramePadding::Solid(Color::from(RED))));
+    assert_eq!(pixels[0], [255, 0, 0, 255]);
+    assert_eq!(pixels[6], [6, 5, 0, 255]);
+    assert_eq!(pixels[15], [255, 0, 0, 255]);
+}
+
+#[test]
+fn empty_frames_are_rejected() {
+    let empty = TileFrame {
+        frame: URect::new(2, 2, 2, 2),
+        anchor: UVec2::ZERO,
+        align: None,
+    };
+    assert!(matches!(
+        import_frames(vec![empty], FramePadding::Extrude),
+        Err(ImportTilesetError::ValidateSource(_))
+    ));
+}
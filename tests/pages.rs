@@ -0,0 +1,83 @@
+use bevy_tileset_importer::{
+    TilesetImportSettings,
+    format::{OutputLayout, split_array_pages},
+    importer::ImportTilesetError,
+};
+use wgpu_types::{TextureDataOrder, TextureViewDimension};
+
+mod common;
+
+#[test]
+fn pages_split_consecutive_tiles() {
+    let settings = TilesetImportSettings {
+        generate_mips: true,
+        ..Default::default()
+    };
+    let file = common::letters().import(&settings).unwrap();
+    let (count, _, image) = file.into_count_groups_image().unwrap();
+    assert_eq!(count, 6);
+
+    let data = image.data.clone().unwrap();
+    let tile_len = data.len() / 6;
+
+    let pages = split_array_pages(image, 4).unwrap();
+    assert_eq!(pages.len(), 2);
+
+    for (page, tiles) in pages.iter().zip([0..4, 4..6]) {
+        let size = page.texture_descriptor.size;
+        assert_eq!(size.depth_or_array_layers as usize, tiles.len());
+        assert_eq!(page.texture_descriptor.mip_level_count, 5);
+        assert_eq!(page.data_order, TextureDataOrder::LayerMajor);
+        assert_eq!(
+            page.texture_view_descriptor.as_ref().unwrap().dimension,
+            Some(TextureViewDimension::D2Array)
+        );
+        assert_eq!(
+            page.data.as_deref().unwrap(),
+            &data[tiles.start * tile_len..tiles.end * tile_len]
+        );
+    }
+}
+
+#[test]
+fn pages_keep_images_that_fit() {
+    let file = common::letters()
+        .import(&TilesetImportSettings::default())
+        .unwrap();
+    let (_, _, image) = file.into_count_groups_image().unwrap();
+
+    let pages = split_array_pages(image.clone(), 6).unwrap();
+    assert_eq!(pages.len(), 1);
+    assert_eq!(pages[0].data, image.data);
+    assert!(pages[0].texture_view_descriptor.is_none());
+}
+
+#[test]
+fn max_array_layers_is_recorded() {
+    let settings = TilesetImportSettings {
+        max_array_layers: Some(4),
+        ..Default::default()
+    };
+    let file = common::letters().import(&settings).unwrap();
+    assert_eq!(file.max_array_layers, Some(4));
+
+    // Atlases are a single texture, so they are never split
+    let atlas = TilesetImportSettings {
+        output_layout: OutputLayout::Atlas {
+            columns: 2,
+            padding: 0,
+        },
+        ..settings.clone()
+    };
+    let file = common::letters().import(&atlas).unwrap();
+    assert_eq!(file.max_array_layers, None);
+
+    let empty = TilesetImportSettings {
+        max_array_layers: Some(0),
+        ..settings
+    };
+    assert!(matches!(
+        common::letters().import(&empty),
+        Err(ImportTilesetError::ArrayLayers)
+    ));
+}
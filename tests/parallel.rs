@@ -0,0 +1,71 @@
+use bevy_tileset_importer::{
+    TilesetImportSettings,
+    format::TilesetFile,
+    importer::{MipFilter, TileTransform, TileVariant, TilesetSource},
+    layout::{FramePadding, TilesetLayout},
+};
+use wgpu_types::TextureFormat;
+
+mod common;
+
+/// Imports the letters with a duplicate source and a group of transformed variants.
+fn import(settings: &TilesetImportSettings) -> TilesetFile {
+    let mut data = common::letters();
+    data.sources.push(TilesetSource {
+        texture: common::load_image("tile_e.png"),
+        layout: TilesetLayout::unpadded_grid(),
+        padding: FramePadding::default(),
+    });
+    data.tile_groups.push((
+        "variants".into(),
+        [
+            TileTransform::FlipX,
+            TileTransform::Rot90,
+            TileTransform::Rot270,
+        ]
+        .into_iter()
+        .map(|transform| TileVariant::new((0, 2), transform))
+        .collect(),
+    ));
+    data.import(settings).unwrap()
+}
+
+/// Asserts that a parallel import is identical to a sequential one.
+fn assert_matches_sequential(settings: TilesetImportSettings) {
+    let parallel = import(&TilesetImportSettings {
+        parallel: true,
+        ..settings.clone()
+    });
+    let sequential = import(&TilesetImportSettings {
+        parallel: false,
+        ..settings
+    });
+    assert_eq!(parallel, sequential);
+}
+
+#[test]
+fn parallel_matches_sequential() {
+    assert_matches_sequential(TilesetImportSettings {
+        generate_mips: true,
+        mip_filter: MipFilter::Lanczos3,
+        ..Default::default()
+    });
+}
+
+#[test]
+fn parallel_matches_sequential_with_dedup() {
+    assert_matches_sequential(TilesetImportSettings {
+        generate_mips: true,
+        dedup_pixels: true,
+        ..Default::default()
+    });
+}
+
+#[test]
+fn parallel_matches_sequential_block_compressed() {
+    assert_matches_sequential(TilesetImportSettings {
+        texture_format: Some(TextureFormat::Bc7RgbaUnormSrgb),
+        generate_mips: true,
+        ..Default::default()
+    });
+}
@@ -0,0 +1,82 @@
+use bevy_asset::RenderAssetUsages;
+use bevy_image::Image;
+use bevy_math::UVec2;
+use bevy_tileset_importer::{
+    TilesetImportSettings,
+    format::{Compression, Storage, TilesetFile, TilesetFileHeader},
+    importer::{AlphaMipMode, TileFilter, TilesetImportData, TilesetSource},
+    layout::{FramePadding, TilesetLayout},
+};
+use wgpu_types::{Extent3d, TextureDimension, TextureFormat};
+
+/// Imports a 2x2 tile with one half-transparent white pixel, and three transparent pixels.
+fn import_half_transparent(premultiply_alpha: bool) -> TilesetFile {
+    let mut data = vec![0; 2 * 2 * 4];
+    data[..4].copy_from_slice(&[255, 255, 255, 128]);
+    let texture = Image::new(
+        Extent3d {
+            width: 2,
+            height: 2,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8Unorm,
+        RenderAssetUsages::all(),
+    );
+
+    let settings = TilesetImportSettings {
+        generate_mips: true,
+        alpha_mip_mode: AlphaMipMode::Premultiplied,
+        premultiply_alpha,
+        ..Default::default()
+    };
+    TilesetImportData {
+        tile_size: UVec2::splat(2),
+        tile_filter: TileFilter::All,
+        tile_groups: Vec::new(),
+        sources: vec![TilesetSource {
+            texture,
+            layout: TilesetLayout::unpadded_grid(),
+            padding: FramePadding::default(),
+        }],
+        layers: Vec::new(),
+    }
+    .import(&settings)
+    .unwrap()
+}
+
+#[test]
+fn straight_alpha_by_default() {
+    let file = import_half_transparent(false);
+    assert!(!file.premultiplied_alpha);
+    assert_eq!(file.texture_data[..4], [255, 255, 255, 128]);
+}
+
+#[test]
+fn premultiply_alpha() {
+    let file = import_half_transparent(true);
+    assert!(file.premultiplied_alpha);
+    assert_eq!(file.texture_data[..4], [128, 128, 128, 128]);
+    // The stored colors are averaged as-is, rather than being weighted by alpha again
+    assert_eq!(file.texture_data[16..], [32, 32, 32, 32]);
+}
+
+#[test]
+fn premultiplied_alpha_round_trip() {
+    let file = import_half_transparent(true);
+
+    let mut bytes = Vec::new();
+    file.write(Compression::None, Storage::Contiguous, &mut bytes)
+        .unwrap();
+    assert!(
+        TilesetFileHeader::read(bytes.as_slice())
+            .unwrap()
+            .premultiplied_alpha
+    );
+    assert_eq!(TilesetFile::read(bytes.as_slice()).unwrap(), file);
+
+    let mut bytes = Vec::new();
+    file.write_ktx2(&mut bytes).unwrap();
+    assert_eq!(TilesetFile::read_ktx2(&bytes).unwrap(), file);
+}
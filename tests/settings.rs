@@ -0,0 +1,89 @@
+use bevy_asset::meta::{AssetAction, AssetMeta};
+use bevy_reflect::TypePath;
+use bevy_tileset_importer::{
+    format::Compression,
+    importer::{AlphaMipMode, MipFilter},
+    layout::GridOrder,
+    process::{ImageLayoutSetting, ImageProcess},
+};
+
+/// Image tileset settings written before any of the newer import settings existed.
+const BASELINE_META: &str = r#"(
+    meta_format_version: "1.0",
+    asset: Process(
+        processor: "PROCESSOR",
+        settings: (
+            source_settings: (
+                layout: Grid(
+                    tile_size: (16, 16),
+                    padding: (1, 1),
+                    margins: (min: (0, 0), max: (0, 0)),
+                ),
+                tile_filter: All,
+                tile_groups: {},
+                format: FromExtension,
+                texture_format: None,
+                is_srgb: true,
+            ),
+            import_settings: (
+                texture_format: None,
+                generate_mips: true,
+                compression: COMPRESSION,
+            ),
+            loader_settings: (
+                sampler: Default,
+                asset_usage: ("RENDER_WORLD"),
+            ),
+        ),
+    ),
+)"#;
+
+fn deserialize(compression: &str) -> AssetMeta<(), ImageProcess> {
+    let meta = BASELINE_META
+        .replace("PROCESSOR", ImageProcess::type_path())
+        .replace("COMPRESSION", compression);
+    AssetMeta::deserialize(meta.as_bytes()).unwrap_or_else(|err| panic!("{err}"))
+}
+
+#[test]
+fn baseline_meta_uses_defaults() {
+    let meta = deserialize("6");
+    let AssetAction::Process { settings, .. } = meta.asset else {
+        panic!("expected a process action");
+    };
+
+    let import = settings.import_settings;
+    assert!(import.generate_mips);
+    assert_eq!(import.compression, Compression::Deflate(6));
+    assert_eq!(import.mip_filter, MipFilter::Box);
+    assert_eq!(import.alpha_mip_mode, AlphaMipMode::DiscardAll);
+    assert!(import.trim.is_none());
+    assert_eq!(import.max_array_layers, None);
+    assert!(import.parallel);
+
+    assert!(matches!(
+        settings.source_settings.layout,
+        ImageLayoutSetting::Grid {
+            order: GridOrder::RowMajor,
+            region: None,
+            ..
+        }
+    ));
+}
+
+#[test]
+fn compression_accepts_every_form() {
+    let cases = [
+        ("None", Compression::None),
+        ("Deflate(9)", Compression::Deflate(9)),
+        ("Zstd(-3)", Compression::Zstd(-3)),
+        ("Lz4", Compression::Lz4),
+    ];
+    for (setting, expected) in cases {
+        let meta = deserialize(setting);
+        let AssetAction::Process { settings, .. } = meta.asset else {
+            panic!("expected a process action");
+        };
+        assert_eq!(settings.import_settings.compression, expected, "{setting}");
+    }
+}
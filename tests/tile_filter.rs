@@ -0,0 +1,68 @@
+use bevy_asset::RenderAssetUsages;
+use bevy_image::Image;
+use bevy_math::UVec2;
+use bevy_tileset_importer::{
+    TilesetImportSettings,
+    importer::{ImportTilesetError, TileFilter, TileVariant, TilesetImportData, TilesetSource},
+    layout::{FramePadding, TilesetLayout},
+};
+use wgpu_types::{Extent3d, TextureDimension, TextureFormat};
+
+mod common;
+
+/// A 3x1 grid of 4x4 tiles, where the middle tile is fully transparent and the last tile is
+/// nearly transparent.
+fn sparse_grid(tile_groups: Vec<(String, Vec<TileVariant>)>) -> TilesetImportData {
+    let mut texture = Image::new_fill(
+        Extent3d {
+            width: 12,
+            height: 4,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        &[0; 4],
+        TextureFormat::Rgba8Unorm,
+        RenderAssetUsages::all(),
+    );
+    let data = texture.data.as_mut().unwrap();
+    for y in 0..4 {
+        for x in 0..4 {
+            data[(y * 12 + x) * 4..][..4].copy_from_slice(&[255; 4]);
+        }
+    }
+    data[(3 * 12 + 11) * 4 + 3] = 1;
+
+    TilesetImportData {
+        tile_size: UVec2::splat(4),
+        tile_filter: TileFilter::NonTransparent {
+            alpha_threshold: 0.01,
+        },
+        tile_groups,
+        sources: vec![TilesetSource {
+            texture,
+            layout: TilesetLayout::unpadded_grid(),
+            padding: FramePadding::default(),
+        }],
+        layers: Vec::new(),
+    }
+}
+
+#[test]
+fn skip_transparent_tiles() {
+    let data = sparse_grid(vec![common::group("opaque", &[(0, 0)])]);
+    let file = data.import(&TilesetImportSettings::default()).unwrap();
+    assert_eq!(file.tile_count, 1);
+    assert_eq!(file.tile_groups, [("opaque".to_string(), vec![0])]);
+}
+
+#[test]
+fn transparent_tile_in_group() {
+    let data = sparse_grid(vec![common::group("empty", &[(0, 1)])]);
+    assert!(matches!(
+        data.import(&TilesetImportSettings::default()),
+        Err(ImportTilesetError::TransparentGroupTile {
+            tile_source: (0, 1),
+            ..
+        })
+    ));
+}
@@ -0,0 +1,100 @@
+use bevy_tileset_importer::{
+    TilesetImportSettings,
+    format::TilesetFile,
+    importer::{ImportTilesetError, TileFilter, TileTransform, TileVariant},
+    process::DataTileset,
+};
+
+mod common;
+
+/// Imports the `a` tile, followed by the given variants of it in a group.
+fn import_variants(transforms: &[TileTransform]) -> Result<TilesetFile, ImportTilesetError> {
+    let mut data = common::letters();
+    data.tile_filter = TileFilter::List(vec![(0, 0)]);
+    data.tile_groups = vec![(
+        "variants".into(),
+        transforms
+            .iter()
+            .map(|&transform| TileVariant::new((0, 0), transform))
+            .collect(),
+    )];
+    data.import(&TilesetImportSettings::default())
+}
+
+/// Returns the RGBA pixel at `(x, y)` of a 16x16 tile.
+fn pixel(file: &TilesetFile, tile: usize, x: usize, y: usize) -> &[u8] {
+    let start = (tile * 16 * 16 + y * 16 + x) * 4;
+    &file.texture_data[start..start + 4]
+}
+
+#[test]
+fn transformed_variants() {
+    use TileTransform::*;
+
+    let transforms = [
+        Identity,
+        FlipX,
+        FlipY,
+        Rot90,
+        Rot180,
+        Rot270,
+        Transpose,
+        AntiTranspose,
+    ];
+    let file = import_variants(&transforms).unwrap();
+
+    // The identity variant reuses the filtered tile, and every other variant is distinct.
+    assert_eq!(file.tile_count, 8);
+    assert_eq!(file.tile_groups[0].1, [0, 1, 2, 3, 4, 5, 6, 7]);
+
+    for y in 0..16 {
+        for x in 0..16 {
+            let (rx, ry) = (15 - x, 15 - y);
+            assert_eq!(pixel(&file, 1, x, y), pixel(&file, 0, rx, y));
+            assert_eq!(pixel(&file, 2, x, y), pixel(&file, 0, x, ry));
+            assert_eq!(pixel(&file, 3, ry, x), pixel(&file, 0, x, y));
+            assert_eq!(pixel(&file, 4, x, y), pixel(&file, 0, rx, ry));
+            assert_eq!(pixel(&file, 5, y, rx), pixel(&file, 0, x, y));
+            assert_eq!(pixel(&file, 6, x, y), pixel(&file, 0, y, x));
+            assert_eq!(pixel(&file, 7, x, y), pixel(&file, 0, ry, rx));
+        }
+    }
+}
+
+#[test]
+fn rotation_requires_square_tiles() {
+    let mut data = common::letters();
+    data.tile_size.x = 8;
+    data.tile_groups = vec![(
+        "r".into(),
+        vec![TileVariant::new((0, 0), TileTransform::Rot90)],
+    )];
+    assert!(matches!(
+        data.import(&TilesetImportSettings::default()),
+        Err(ImportTilesetError::NonSquareTransform { .. })
+    ));
+}
+
+#[test]
+fn parse_group_variants() {
+    let data: DataTileset = ron::from_str(
+        r#"(
+            tile_size: (16, 16),
+            sources: [],
+            tile_groups: { "g": [(0, 1), (2, 3, FlipX)] },
+        )"#,
+    )
+    .unwrap();
+
+    assert_eq!(
+        data.tile_groups["g"],
+        [
+            TileVariant::from((0, 1)),
+            TileVariant::new((2, 3), TileTransform::FlipX)
+        ]
+    );
+    assert_eq!(
+        ron::to_string(&data.tile_groups["g"]).unwrap(),
+        "[(0,1),(2,3,FlipX)]"
+    );
+}
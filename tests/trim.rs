@@ -0,0 +1,144 @@
+use bevy_asset::RenderAssetUsages;
+use bevy_image::Image;
+use bevy_math::{URect, UVec2};
+use bevy_tileset_importer::{
+    TilesetImportSettings,
+    format::{Compression, Storage, TileTrim, TilesetFile},
+    importer::{
+        FrameTrim, TileFilter, TileTransform, TileVariant, TilesetImportData, TilesetSource,
+    },
+    layout::{FrameAlign, FramePadding, TilesetLayout},
+};
+use wgpu_types::{Extent3d, TextureDimension, TextureFormat};
+
+/// Imports two 4x4 tiles: one with a 2x1 bar at (1, 2), and one that is fully transparent.
+/// The first tile is also imported rotated by 90 degrees.
+fn import(trim: Option<FrameTrim>) -> TilesetFile {
+    import_variants(trim, vec![TileVariant::new((0, 0), TileTransform::Rot90)])
+}
+
+/// Imports the two tiles described by [`import`], followed by `variants`.
+fn import_variants(trim: Option<FrameTrim>, variants: Vec<TileVariant>) -> TilesetFile {
+    let mut texture = Image::new_fill(
+        Extent3d {
+            width: 8,
+            height: 4,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        &[0; 4],
+        TextureFormat::Rgba8Unorm,
+        RenderAssetUsages::all(),
+    );
+    let data = texture.data.as_mut().unwrap();
+    for x in 1..3 {
+        let i = (2 * 8 + x) * 4;
+        data[i..i + 4].copy_from_slice(&[255, 0, 0, 255]);
+    }
+
+    TilesetImportData {
+        tile_size: UVec2::splat(4),
+        tile_filter: TileFilter::All,
+        tile_groups: vec![("variants".into(), variants)],
+        sources: vec![TilesetSource {
+            texture,
+            layout: TilesetLayout::unpadded_grid(),
+            padding: FramePadding::default(),
+        }],
+        layers: Vec::new(),
+    }
+    .import(&TilesetImportSettings {
+        trim,
+        ..Default::default()
+    })
+    .unwrap()
+}
+
+#[test]
+fn trims_are_recorded_per_tile() {
+    let file = import(Some(FrameTrim {
+        alpha_threshold: 0.5,
+        align: Some(FrameAlign::TopLeft),
+    }));
+    assert_eq!(file.tile_count, 3);
+
+    let expected = [
+        TileTrim {
+            offset: UVec2::new(1, 2),
+            original_size: UVec2::splat(4),
+            rect: URect::new(0, 0, 2, 1),
+        },
+        // Nothing is visible, so the frame is kept
+        TileTrim {
+            offset: UVec2::ZERO,
+            original_size: UVec2::splat(4),
+            rect: URect::new(0, 0, 0, 0),
+        },
+        // The bar becomes vertical
+        TileTrim {
+            offset: UVec2::new(1, 2),
+            original_size: UVec2::splat(4),
+            rect: URect::new(3, 0, 4, 2),
+        },
+    ];
+    assert_eq!(file.tile_trims, expected);
+
+    // The trimmed bar is moved to the top left
+    let opaque = file.texture_data[..64]
+        .chunks_exact(4)
+        .map(|pixel| pixel[3] == 255)
+        .collect::<Vec<_>>();
+    assert_eq!(&opaque[..4], [true, true, false, false]);
+    assert!(opaque[4..].iter().all(|&opaque| !opaque));
+
+    let mut bytes = Vec::new();
+    file.write(Compression::None, Storage::Contiguous, &mut bytes)
+        .unwrap();
+    let read = TilesetFile::read(bytes.as_slice()).unwrap();
+    assert_eq!(read.tile_trims, expected);
+}
+
+#[test]
+fn unaligned_trims_keep_pixels_in_place() {
+    let untrimmed = import(None);
+    let trimmed = import(Some(FrameTrim {
+        alpha_threshold: 0.5,
+        align: None,
+    }));
+
+    assert!(untrimmed.tile_trims.is_empty());
+    assert_eq!(trimmed.texture_data, untrimmed.texture_data);
+    assert_eq!(trimmed.tile_trims[0].rect, URect::new(1, 2, 3, 3));
+    assert_eq!(trimmed.tile_trims[2].rect, URect::new(1, 1, 2, 3));
+}
+
+#[test]
+fn empty_trims_are_transformed() {
+    let trim = FrameTrim {
+        alpha_threshold: 0.5,
+        align: None,
+    };
+    let file = import_variants(
+        Some(trim),
+        vec![
+            TileVariant::new((0, 1), TileTransform::FlipX),
+            TileVariant::new((0, 1), TileTransform::Rot180),
+        ],
+    );
+    assert_eq!(file.tile_count, 4);
+
+    // The empty rect sits at the anchor of the frame, which is its top left corner
+    let rects = file
+        .tile_trims
+        .iter()
+        .map(|trim| trim.rect)
+        .collect::<Vec<_>>();
+    assert_eq!(
+        rects[1..],
+        [
+            URect::new(0, 0, 0, 0),
+            URect::new(4, 0, 4, 0),
+            URect::new(4, 4, 4, 4),
+        ]
+    );
+}
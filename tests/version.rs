@@ -0,0 +1,46 @@
+use bevy_asset::RenderAssetUsages;
+use bevy_image::Image;
+use bevy_tileset_importer::{
+    TileGroups,
+    format::{Compression, FORMAT_VERSION, Storage, TilesetFile, TilesetFileError},
+    loader::TilesetLoaderError,
+};
+use wgpu_types::{Extent3d, TextureDimension, TextureFormat};
+
+fn file_bytes() -> Vec<u8> {
+    let image = Image::new_fill(
+        Extent3d {
+            width: 4,
+            height: 4,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        &[255, 0, 0, 255],
+        TextureFormat::Rgba8Unorm,
+        RenderAssetUsages::default(),
+    );
+    let file = TilesetFile::new(TileGroups::default(), image).unwrap();
+
+    let mut bytes = Vec::new();
+    file.write(Compression::None, Storage::Contiguous, &mut bytes)
+        .unwrap();
+    bytes
+}
+
+#[test]
+fn newer_versions_are_not_stale() {
+    let mut bytes = file_bytes();
+
+    // The format version follows the magic bytes
+    bytes[4..6].copy_from_slice(&(FORMAT_VERSION + 1).to_le_bytes());
+    let err = TilesetFile::read(bytes.as_slice()).unwrap_err();
+    assert!(
+        matches!(err, TilesetFileError::UnsupportedVersion(v) if v == FORMAT_VERSION + 1),
+        "{err}"
+    );
+    assert!(err.is_newer_version());
+    assert!(!err.is_stale());
+
+    let err = TilesetLoaderError::from(err);
+    assert!(matches!(err, TilesetLoaderError::NewerVersion(_)), "{err}");
+}